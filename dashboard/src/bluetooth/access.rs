@@ -0,0 +1,189 @@
+use defmt::{info, warn};
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::Mutex};
+use heapless::Vec;
+use periphery_protocol::access::{evicted_bond, initial_roles};
+use periphery_protocol::hash::fnv1a;
+use trouble_host::prelude::*;
+
+use crate::error::Error;
+use crate::storage::{BONDS_OFFSET, STORAGE};
+
+pub use periphery_protocol::access::{RoleAssignment, Roles};
+
+/// Number of bonded centrals remembered by the device
+pub const MAX_BONDS: usize = 4;
+/// Marks a valid bond table in flash
+const MAGIC: u32 = 0x424f_4e44;
+/// Encoded as `[identity address (6 bytes), has irk, irk (u128), ltk (u128), roles]`
+const BOND_LEN: usize = 40;
+/// Magic (u32) and bond count in front of the bonds, checksum (u32) following them
+const TABLE_LEN: usize = 5 + MAX_BONDS * BOND_LEN + 4;
+
+static BONDS: Mutex<CriticalSectionRawMutex, Vec<Bond, MAX_BONDS>> = Mutex::new(Vec::new());
+
+#[derive(Clone)]
+struct Bond {
+    info: BondInformation,
+    roles: Roles,
+}
+
+impl Bond {
+    fn encode(&self, buf: &mut [u8]) {
+        let identity = &self.info.identity;
+        buf[0..6].copy_from_slice(identity.bd_addr.raw());
+        buf[6] = identity.irk.is_some() as u8;
+        let irk = identity.irk.map_or(0, |irk| irk.0);
+        buf[7..23].copy_from_slice(&irk.to_le_bytes());
+        buf[23..39].copy_from_slice(&self.info.ltk.0.to_le_bytes());
+        buf[39] = self.roles.bits();
+    }
+
+    fn decode(buf: &[u8]) -> Option<Self> {
+        let mut addr = [0u8; 6];
+        addr.copy_from_slice(&buf[0..6]);
+        let irk = u128::from_le_bytes(buf[7..23].try_into().ok()?);
+        let ltk = u128::from_le_bytes(buf[23..39].try_into().ok()?);
+        Some(Bond {
+            info: BondInformation {
+                identity: Identity {
+                    bd_addr: BdAddr::new(addr),
+                    irk: (buf[6] != 0).then_some(IdentityResolvingKey(irk)),
+                },
+                ltk: LongTermKey(ltk),
+                security_level: SecurityLevel::Encrypted,
+                is_bonded: true,
+            },
+            roles: Roles::from_bits(buf[39]).ok()?,
+        })
+    }
+}
+
+/// Load the bonds from flash and hand them to the host, so bonded centrals can reconnect.
+pub async fn load<C: Controller, P: PacketPool>(stack: &Stack<'_, C, P>) {
+    let mut table = [0u8; TABLE_LEN];
+    let result = {
+        let mut storage = STORAGE.lock().await;
+        match storage.as_mut() {
+            Some(storage) => storage.read(BONDS_OFFSET, &mut table),
+            None => Err(Error::StorageUnavailable),
+        }
+    };
+    if let Err(e) = result {
+        warn!("[access] error reading bonds: {:?}", e);
+        return;
+    }
+
+    let magic = u32::from_le_bytes([table[0], table[1], table[2], table[3]]);
+    let count = (table[4] as usize).min(MAX_BONDS);
+    let end = 5 + count * BOND_LEN;
+    let checksum = u32::from_le_bytes([table[end], table[end + 1], table[end + 2], table[end + 3]]);
+    if magic != MAGIC || checksum != fnv1a(&table[..end]) {
+        info!("[access] no stored bonds");
+        return;
+    }
+
+    let mut bonds = BONDS.lock().await;
+    for encoded in table[5..end].chunks_exact(BOND_LEN) {
+        let Some(bond) = Bond::decode(encoded) else {
+            continue;
+        };
+        if let Err(e) = stack.add_bond_information(bond.info.clone()) {
+            warn!("[access] error restoring bond: {:?}", e);
+        }
+        let _ = bonds.push(bond);
+    }
+    info!("[access] restored {} bonds", bonds.len());
+}
+
+/// Remember a new bond. The first bonded central becomes hub and admin, later ones
+/// start without any role until an admin assigns one.
+///
+/// If the table is full, the oldest bond without the admin role is forgotten. The new bond
+/// is refused if only admins are left.
+pub async fn add_bond(info: BondInformation) -> Result<(), Error> {
+    let mut bonds = BONDS.lock().await;
+    let roles = initial_roles(bonds.len());
+    let roles = match bonds
+        .iter()
+        .position(|bond| bond.info.identity.bd_addr == info.identity.bd_addr)
+    {
+        Some(index) => bonds.remove(index).roles,
+        None => roles,
+    };
+    if bonds.is_full() {
+        let evicted = evicted_bond(bonds.iter().map(|bond| bond.roles)).ok_or(Error::BondsFull)?;
+        bonds.remove(evicted);
+    }
+    let _ = bonds.push(Bond { info, roles });
+    info!("[access] bonded with roles {:?}", roles);
+    store(&bonds).await
+}
+
+/// Assign roles to a bonded central, see [`RoleAssignment`].
+pub async fn assign_roles(data: &[u8]) -> Result<(), Error> {
+    let assignment = RoleAssignment::decode(data)?;
+
+    let mut bonds = BONDS.lock().await;
+    let bond = bonds
+        .iter_mut()
+        .find(|bond| bond.info.identity.bd_addr.raw() == assignment.address)
+        .ok_or(Error::InvalidValue)?;
+    bond.roles = assignment.roles;
+    info!("[access] assigned roles {:?}", assignment.roles);
+    store(&bonds).await
+}
+
+/// Roles of the central on the other end of `conn`.
+///
+/// Roles are only granted on encrypted links, where the peer identity has been proven.
+pub async fn roles_of<P: PacketPool>(conn: &Connection<'_, P>) -> Roles {
+    if !is_encrypted(conn) {
+        return Roles::VIEWER;
+    }
+    let identity = conn.peer_identity();
+    BONDS
+        .lock()
+        .await
+        .iter()
+        .find(|bond| bond.info.identity.bd_addr == identity.bd_addr)
+        .map_or(Roles::VIEWER, |bond| bond.roles)
+}
+
+/// Check that the central on the other end of `conn` has been granted `required`.
+pub async fn authorize<P: PacketPool>(
+    conn: &Connection<'_, P>,
+    required: Roles,
+) -> Result<(), Error> {
+    if !is_encrypted(conn) {
+        // makes the central pair and bond before retrying
+        return Err(Error::Unauthenticated);
+    }
+    if roles_of(conn).await.contains(required) {
+        Ok(())
+    } else {
+        Err(Error::Unauthorized)
+    }
+}
+
+fn is_encrypted<P: PacketPool>(conn: &Connection<'_, P>) -> bool {
+    matches!(
+        conn.security_level(),
+        Ok(SecurityLevel::Encrypted | SecurityLevel::EncryptedAuthenticated)
+    )
+}
+
+async fn store(bonds: &[Bond]) -> Result<(), Error> {
+    let mut table = [0xFFu8; TABLE_LEN];
+    table[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+    table[4] = bonds.len() as u8;
+    for (bond, buf) in bonds.iter().zip(table[5..].chunks_exact_mut(BOND_LEN)) {
+        bond.encode(buf);
+    }
+    let end = 5 + bonds.len() * BOND_LEN;
+    let checksum = fnv1a(&table[..end]);
+    table[end..end + 4].copy_from_slice(&checksum.to_le_bytes());
+
+    let mut storage = STORAGE.lock().await;
+    let storage = storage.as_mut().ok_or(Error::StorageUnavailable)?;
+    storage.write(BONDS_OFFSET, &table)
+}
@@ -0,0 +1,138 @@
+use embassy_time::{Duration, Instant};
+use trouble_host::prelude::{AdStructure, AdvertisementParameters};
+
+use crate::error::Error;
+use crate::state::{DeviceState, FIRMWARE_VERSION};
+
+/// Company identifier reserved by the Bluetooth SIG for testing
+pub const COMPANY_ID: u16 = 0xFFFF;
+/// Encoded as `[record type, version (3 bytes), battery (%, 0xFF if unknown), flags,
+/// image hash prefix (u32)]`, little endian
+pub const MANUFACTURER_DATA_LEN: usize = 10;
+/// Encoded as `[record type, uptime (u32, s), battery (%, 0xFF if unknown), last image id
+/// (u32), error flags]`, little endian
+pub const BEACON_DATA_LEN: usize = 11;
+const RECORD_DEVICE_STATE: u8 = 0x01;
+const RECORD_BEACON: u8 = 0x02;
+/// Non-connectable advertising may not use intervals below 100 ms
+pub const BEACON_INTERVAL: Duration = Duration::from_millis(100);
+/// How long each status beacon is broadcast
+pub const BEACON_DURATION: Duration = Duration::from_secs(1);
+/// Battery level reported until a measurement exists, so the record layout stays fixed
+const BATTERY_UNKNOWN: u8 = 0xFF;
+const FLAG_NEEDS_UPDATE: u8 = 1 << 0;
+
+/// Advertising intervals allowed by the core specification
+const MIN_INTERVAL_MS: u16 = 20;
+const MAX_INTERVAL_MS: u16 = 10_240;
+
+/// Advertise with a short interval after disconnecting, then fall back to a long interval
+/// to save power.
+///
+/// While advertising with the slow interval, a non-connectable status beacon can be
+/// broadcast every `beacon_period`, so the hub can monitor the device without connecting.
+#[derive(Clone, Copy, defmt::Format)]
+pub struct AdvertisingConfig {
+    pub fast_interval: Duration,
+    pub fast_timeout: Duration,
+    pub slow_interval: Duration,
+    pub beacon_period: Option<Duration>,
+}
+
+impl Default for AdvertisingConfig {
+    fn default() -> Self {
+        AdvertisingConfig {
+            fast_interval: Duration::from_millis(100),
+            fast_timeout: Duration::from_secs(30),
+            slow_interval: Duration::from_millis(1000),
+            beacon_period: None,
+        }
+    }
+}
+
+impl AdvertisingConfig {
+    /// Encoded as `[fast interval (u16, ms), fast timeout (u16, s), slow interval (u16, ms),
+    /// beacon period (u16, s, 0 disables the beacon)]`, little endian.
+    pub const ENCODED_LEN: usize = 8;
+
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut encoded = [0u8; Self::ENCODED_LEN];
+        encoded[0..2].copy_from_slice(&(self.fast_interval.as_millis() as u16).to_le_bytes());
+        encoded[2..4].copy_from_slice(&(self.fast_timeout.as_secs() as u16).to_le_bytes());
+        encoded[4..6].copy_from_slice(&(self.slow_interval.as_millis() as u16).to_le_bytes());
+        let beacon_period = self
+            .beacon_period
+            .map_or(0, |period| period.as_secs() as u16);
+        encoded[6..8].copy_from_slice(&beacon_period.to_le_bytes());
+        encoded
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let data: &[u8; Self::ENCODED_LEN] = data.try_into().map_err(|_| Error::InvalidLength)?;
+        let fast_interval = u16::from_le_bytes([data[0], data[1]]);
+        let fast_timeout = u16::from_le_bytes([data[2], data[3]]);
+        let slow_interval = u16::from_le_bytes([data[4], data[5]]);
+        let beacon_period = u16::from_le_bytes([data[6], data[7]]);
+
+        let valid = MIN_INTERVAL_MS..=MAX_INTERVAL_MS;
+        if !valid.contains(&fast_interval) || !valid.contains(&slow_interval) {
+            return Err(Error::InvalidValue);
+        }
+        Ok(AdvertisingConfig {
+            fast_interval: Duration::from_millis(fast_interval.into()),
+            fast_timeout: Duration::from_secs(fast_timeout.into()),
+            slow_interval: Duration::from_millis(slow_interval.into()),
+            beacon_period: (beacon_period != 0).then(|| Duration::from_secs(beacon_period.into())),
+        })
+    }
+}
+
+pub fn parameters(interval: Duration) -> AdvertisementParameters {
+    AdvertisementParameters {
+        interval_min: interval,
+        interval_max: interval,
+        ..Default::default()
+    }
+}
+
+/// Longest name fitting into the scan response next to the 128 bit service UUID
+const SCAN_NAME_MAX_LEN: usize = 11;
+
+/// Advertise the complete name if it fits into the scan response, otherwise shorten it.
+pub fn local_name(name: &str) -> AdStructure<'_> {
+    if name.len() <= SCAN_NAME_MAX_LEN {
+        return AdStructure::CompleteLocalName(name.as_bytes());
+    }
+    let end = (0..=SCAN_NAME_MAX_LEN)
+        .rev()
+        .find(|&i| name.is_char_boundary(i))
+        .unwrap_or(0);
+    AdStructure::ShortenedLocalName(&name.as_bytes()[..end])
+}
+
+pub fn manufacturer_data(state: &DeviceState) -> [u8; MANUFACTURER_DATA_LEN] {
+    let mut flags = 0;
+    if state.needs_update {
+        flags |= FLAG_NEEDS_UPDATE;
+    }
+
+    let mut encoded = [0u8; MANUFACTURER_DATA_LEN];
+    encoded[0] = RECORD_DEVICE_STATE;
+    encoded[1..4].copy_from_slice(&FIRMWARE_VERSION);
+    encoded[4] = state.battery.unwrap_or(BATTERY_UNKNOWN);
+    encoded[5] = flags;
+    encoded[6..10].copy_from_slice(&state.image_hash.to_le_bytes());
+    encoded
+}
+
+pub fn beacon_data(state: &DeviceState) -> [u8; BEACON_DATA_LEN] {
+    let uptime = Instant::now().as_secs() as u32;
+
+    let mut encoded = [0u8; BEACON_DATA_LEN];
+    encoded[0] = RECORD_BEACON;
+    encoded[1..5].copy_from_slice(&uptime.to_le_bytes());
+    encoded[5] = state.battery.unwrap_or(BATTERY_UNKNOWN);
+    encoded[6..10].copy_from_slice(&state.image_hash.to_le_bytes());
+    encoded[10] = state.error_flags;
+    encoded
+}
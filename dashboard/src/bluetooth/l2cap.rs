@@ -0,0 +1,163 @@
+use defmt::{info, warn};
+use periphery_protocol::compression::Decompressor;
+use periphery_protocol::status::TransferStatus;
+use periphery_protocol::transfer::{ChunkAssembler, L2CAP_COMPRESSED_PSM, L2CAP_MTU, L2CAP_PSM};
+use trouble_host::prelude::*;
+
+use crate::bluetooth::access::{self, Roles};
+use crate::bluetooth::link::Activity;
+use crate::bluetooth::peripheral::{Controller, PROGRESS_INTERVAL, commit_frame, report_status};
+use crate::bluetooth::profile::Server;
+use crate::bluetooth::transfer::claim_upload;
+use crate::display::Display;
+use crate::error::Error as FirmwareError;
+use crate::slots;
+
+/// Accept image transfers over an LE credit based L2CAP channel.
+///
+/// The hub streams the encoded frame (the same chunks written to `write_buffer`) from the
+/// first chunk onwards, split into SDUs of any size. On the compressed channel the stream
+/// is PackBits encoded, see [`periphery_protocol::compression`]. Once the last chunk of
+/// the frame has been received, it gets displayed and the next frame can be sent on the
+/// same channel.
+pub async fn l2cap_task<'a>(
+    stack: &'a Stack<'a, Controller, DefaultPacketPool>,
+    server: &Server<'_>,
+    conn: &GattConnection<'_, '_, DefaultPacketPool>,
+    activity: &Activity,
+) {
+    let config = L2capChannelConfig {
+        mtu: Some(L2CAP_MTU),
+        ..Default::default()
+    };
+    loop {
+        let psm = [L2CAP_PSM, L2CAP_COMPRESSED_PSM];
+        let mut channel = match L2capChannel::accept(stack, conn.raw(), &psm, &config).await {
+            Ok(channel) => channel,
+            Err(e) => {
+                let e = defmt::Debug2Format(&e);
+                warn!("[l2cap] error accepting channel: {:?}", e);
+                return;
+            }
+        };
+        let compressed = channel.psm() == L2CAP_COMPRESSED_PSM;
+        info!("[l2cap] channel accepted, compressed: {}", compressed);
+
+        let mut frame = FrameReceiver::new(compressed);
+        let mut sdu = [0u8; L2CAP_MTU as usize];
+        loop {
+            let len = match channel.receive(stack, &mut sdu).await {
+                Ok(len) => len,
+                Err(e) => {
+                    let e = defmt::Debug2Format(&e);
+                    warn!("[l2cap] channel closed: {:?}", e);
+                    // a frame cut off by the closed channel is never committed
+                    slots::end_upload(conn.raw().handle()).await;
+                    break;
+                }
+            };
+            activity.signal(());
+
+            let handle = conn.raw().handle();
+            let result = match access::authorize(conn.raw(), Roles::HUB).await {
+                Ok(()) => claim_upload(handle),
+                Err(e) => Err(e),
+            };
+            let before = frame.received_bytes();
+            let result = match result {
+                Ok(()) => frame.receive(&sdu[..len]).await,
+                Err(e) => Err(e),
+            };
+            let result = match result {
+                Ok(true) => {
+                    let excess = frame.assembler.excess();
+                    frame = FrameReceiver::new(compressed);
+                    let result = commit_frame(server, conn).await;
+                    slots::end_upload(handle).await;
+                    match result {
+                        Ok(()) if excess > 0 => {
+                            warn!("[l2cap] {} bytes after the last chunk", excess);
+                            Err(FirmwareError::InvalidLength)
+                        }
+                        result => result,
+                    }
+                }
+                Ok(false) => {
+                    frame.report_progress(server, conn, before).await;
+                    Ok(())
+                }
+                Err(e) => {
+                    frame = FrameReceiver::new(compressed);
+                    slots::end_upload(handle).await;
+                    Err(e)
+                }
+            };
+            if let Err(e) = result {
+                warn!("[l2cap] transfer failed: {:?}", e);
+                report_status(server, conn, (&e).into()).await;
+            }
+        }
+    }
+}
+
+/// Writes the chunks reassembled from SDUs into the display buffer.
+struct FrameReceiver {
+    assembler: ChunkAssembler,
+    /// Set on the compressed channel
+    decompressor: Option<Decompressor>,
+}
+
+impl FrameReceiver {
+    fn new(compressed: bool) -> Self {
+        FrameReceiver {
+            assembler: ChunkAssembler::new(),
+            decompressor: compressed.then(Decompressor::new),
+        }
+    }
+
+    /// Write the received data into the display buffer, returning true once the frame is complete.
+    async fn receive(&mut self, data: &[u8]) -> Result<bool, FirmwareError> {
+        let mut guard = crate::display::DISPLAY.lock().await;
+        let display = guard.as_mut().ok_or(FirmwareError::DisplayUnavailable)?;
+        slots::park_shown(display).await?;
+
+        let assembler = &mut self.assembler;
+        match &mut self.decompressor {
+            Some(decompressor) => {
+                decompressor.feed(data, |data| write(assembler, display, data))?
+            }
+            None => write(assembler, display, data)?,
+        }
+        Ok(self.assembler.is_complete())
+    }
+
+    /// Bytes of the encoded frame received so far, after decompression.
+    fn received_bytes(&self) -> u32 {
+        self.assembler.received_bytes()
+    }
+
+    /// Notify the hub if the bytes received since `before` crossed a [`PROGRESS_INTERVAL`].
+    async fn report_progress(
+        &self,
+        server: &Server<'_>,
+        conn: &GattConnection<'_, '_, DefaultPacketPool>,
+        before: u32,
+    ) {
+        let bytes = self.received_bytes();
+        if before / PROGRESS_INTERVAL != bytes / PROGRESS_INTERVAL {
+            report_status(server, conn, TransferStatus::Receiving { bytes }).await;
+        }
+    }
+}
+
+/// Write decompressed data into the display buffer, chunk by chunk.
+fn write(
+    assembler: &mut ChunkAssembler,
+    display: &mut Display,
+    data: &[u8],
+) -> Result<(), FirmwareError> {
+    assembler.push(data, |index, chunk| {
+        display.frame_mut().write_to_buffer(chunk, index)?;
+        Ok(())
+    })
+}
@@ -0,0 +1,149 @@
+use defmt::{info, warn};
+use embassy_futures::select::{Either, select};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use embassy_time::{Duration, Timer};
+use trouble_host::prelude::*;
+
+use crate::bluetooth::peripheral::Controller;
+
+/// Time without transfer activity after which the link is relaxed again
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Signaled whenever image data is received, to keep the link in transfer mode.
+pub type Activity = Signal<NoopRawMutex, ()>;
+
+#[derive(Clone, Copy, PartialEq, Eq, defmt::Format)]
+enum LinkMode {
+    /// Short connection interval and LE 2M PHY for maximum throughput
+    Transfer,
+    /// Long connection interval with peripheral latency to save power
+    Idle,
+}
+
+impl LinkMode {
+    fn params(&self) -> RequestedConnParams {
+        match self {
+            LinkMode::Transfer => RequestedConnParams {
+                min_connection_interval: Duration::from_micros(7_500),
+                max_connection_interval: Duration::from_millis(15),
+                max_latency: 0,
+                supervision_timeout: Duration::from_secs(4),
+                ..Default::default()
+            },
+            LinkMode::Idle => RequestedConnParams {
+                min_connection_interval: Duration::from_millis(500),
+                max_connection_interval: Duration::from_millis(1000),
+                max_latency: 4,
+                supervision_timeout: Duration::from_secs(20),
+                ..Default::default()
+            },
+        }
+    }
+
+    async fn apply(
+        &self,
+        stack: &Stack<'_, Controller, DefaultPacketPool>,
+        conn: &GattConnection<'_, '_, DefaultPacketPool>,
+    ) {
+        info!("[link] switching to {:?} mode", self);
+        if let Err(e) = conn
+            .raw()
+            .update_connection_params(stack, &self.params())
+            .await
+        {
+            let e = defmt::Debug2Format(&e);
+            warn!("[link] error updating connection parameters: {:?}", e);
+        }
+        if *self == LinkMode::Transfer
+            && let Err(e) = conn.raw().set_phy(stack, PhyKind::Le2M).await
+        {
+            let e = defmt::Debug2Format(&e);
+            warn!("[link] error switching to LE 2M PHY: {:?}", e);
+        }
+    }
+}
+
+/// Request a fast link while image data is being received and relax it once the
+/// transfer has been idle for [`IDLE_TIMEOUT`].
+pub async fn link_task(
+    stack: &Stack<'_, Controller, DefaultPacketPool>,
+    conn: &GattConnection<'_, '_, DefaultPacketPool>,
+    activity: &Activity,
+) {
+    let mut current = None;
+    loop {
+        let next = if current == Some(LinkMode::Idle) {
+            activity.wait().await;
+            LinkMode::Transfer
+        } else {
+            match select(activity.wait(), Timer::after(IDLE_TIMEOUT)).await {
+                Either::First(()) => LinkMode::Transfer,
+                Either::Second(()) => LinkMode::Idle,
+            }
+        };
+        if current != Some(next) {
+            next.apply(stack, conn).await;
+            current = Some(next);
+        }
+    }
+}
+
+/// Parameters negotiated for the current connection, reported for diagnostics.
+///
+/// Encoded as `[interval (u16, 1.25 ms units), peripheral latency (u16),
+/// supervision timeout (u16, 10 ms units), tx PHY, rx PHY]`, little endian.
+#[derive(Clone, Copy, defmt::Format)]
+pub struct LinkInfo {
+    interval: Duration,
+    latency: u16,
+    supervision_timeout: Duration,
+    tx_phy: u8,
+    rx_phy: u8,
+}
+
+impl LinkInfo {
+    pub const ENCODED_LEN: usize = 8;
+
+    /// Link info of a new connection, which always starts out on the LE 1M PHY.
+    pub fn new(params: ConnParams) -> Self {
+        LinkInfo {
+            interval: params.conn_interval,
+            latency: params.peripheral_latency,
+            supervision_timeout: params.supervision_timeout,
+            tx_phy: phy_code(PhyKind::Le1M),
+            rx_phy: phy_code(PhyKind::Le1M),
+        }
+    }
+
+    pub fn set_params(&mut self, interval: Duration, latency: u16, supervision_timeout: Duration) {
+        self.interval = interval;
+        self.latency = latency;
+        self.supervision_timeout = supervision_timeout;
+    }
+
+    pub fn set_phy(&mut self, tx_phy: PhyKind, rx_phy: PhyKind) {
+        self.tx_phy = phy_code(tx_phy);
+        self.rx_phy = phy_code(rx_phy);
+    }
+
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let interval = (self.interval.as_micros() / 1250) as u16;
+        let timeout = (self.supervision_timeout.as_millis() / 10) as u16;
+
+        let mut encoded = [0u8; Self::ENCODED_LEN];
+        encoded[0..2].copy_from_slice(&interval.to_le_bytes());
+        encoded[2..4].copy_from_slice(&self.latency.to_le_bytes());
+        encoded[4..6].copy_from_slice(&timeout.to_le_bytes());
+        encoded[6] = self.tx_phy;
+        encoded[7] = self.rx_phy;
+        encoded
+    }
+}
+
+fn phy_code(phy: PhyKind) -> u8 {
+    match phy {
+        PhyKind::Le1M => 1,
+        PhyKind::Le2M => 2,
+        _ => 3,
+    }
+}
@@ -0,0 +1,8 @@
+pub mod access;
+pub mod advertising;
+pub mod controller;
+pub mod l2cap;
+pub mod link;
+pub mod peripheral;
+pub mod profile;
+pub mod transfer;
@@ -0,0 +1,725 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use cyw43::bluetooth::BtDriver;
+use defmt::{error, info, warn};
+use embassy_executor::Spawner;
+use embassy_futures::select::{Either, select, select3};
+use embassy_rp::peripherals::TRNG;
+use embassy_rp::trng::Trng;
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, signal::Signal};
+use embassy_time::Timer;
+use heapless::String;
+use periphery_protocol::status::TransferStatus;
+use periphery_protocol::time::CurrentTime;
+use periphery_protocol::transfer::split_stream;
+use static_cell::StaticCell;
+use trouble_host::prelude::*;
+use trouble_host::{Address, HostResources, prelude::DefaultPacketPool};
+
+use crate::bluetooth::access::{self, Roles};
+use crate::bluetooth::advertising::{self, AdvertisingConfig};
+use crate::bluetooth::l2cap::l2cap_task;
+use crate::bluetooth::link::{Activity, LinkInfo, link_task};
+use crate::bluetooth::profile::{DASHBOARD_UUID, Server};
+use crate::bluetooth::transfer::{Transfer, claim_upload, may_write};
+use crate::clock;
+use crate::display::{CHUNK_LEN, OrientationConfig, Refresh};
+use crate::error::Error as FirmwareError;
+use crate::fonts;
+use crate::layout;
+use crate::refresh::{self, RefreshPolicy};
+use crate::schedule::{self, SCHEDULE_CHANGED};
+use crate::settings::{self, NAME_MAX_LEN, Settings};
+use crate::slots::{self, SLIDESHOW_CHANGED, SlideshowConfig};
+use crate::state::{ERROR_TRANSFER_FAILED, device_state, record_refresh, update_device_state};
+use crate::temperature::{self, TEMPERATURE_UNKNOWN};
+use crate::widget::{self, WIDGET_CHANGED, WidgetConfig};
+
+/// A hub plus e.g. a technician's phone reading diagnostics
+const CONNECTIONS_MAX: usize = 2;
+const L2CAP_CHANNELS_MAX: usize = 3; // Signal + att + image channel
+/// Number of received bytes between two progress notifications
+pub(super) const PROGRESS_INTERVAL: u32 = 3200;
+type BleHostResource = HostResources<DefaultPacketPool, CONNECTIONS_MAX, L2CAP_CHANNELS_MAX>;
+pub(super) type Controller = ExternalController<BtDriver<'static>, 10>;
+
+static ACTIVE_CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+static CONNECTION_CLOSED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+#[embassy_executor::task]
+async fn host_task(
+    mut runner: Runner<'static, ExternalController<BtDriver<'static>, 10>, DefaultPacketPool>,
+) {
+    if let Err(e) = runner.run().await {
+        let e = defmt::Debug2Format(&e);
+        error!("[host] runner stopped: {:?}", e);
+    }
+}
+
+#[embassy_executor::task(pool_size = CONNECTIONS_MAX)]
+async fn connection_task(
+    stack: &'static Stack<'static, Controller, DefaultPacketPool>,
+    server: &'static Server<'static>,
+    conn: GattConnection<'static, 'static, DefaultPacketPool>,
+) {
+    update_data_length(stack, &conn).await;
+    // bonding binds the roles to the identity of the central
+    if let Err(e) = conn.raw().set_bondable(true) {
+        warn!("[access] error enabling bonding: {:?}", e);
+    }
+    // set up tasks when the connection is established to a central, so they don't run when no one is connected.
+    let activity = Activity::new();
+    let a = gatt_events_task(server, &conn, &activity);
+    let b = l2cap_task(stack, server, &conn, &activity);
+    let c = link_task(stack, &conn, &activity);
+    // run until any task ends (usually because the connection has been closed)
+    _ = select3(a, b, c).await;
+
+    slots::end_upload(conn.raw().handle()).await;
+    ACTIVE_CONNECTIONS.fetch_sub(1, Ordering::Relaxed);
+    CONNECTION_CLOSED.signal(());
+}
+
+pub async fn run(
+    controller: ExternalController<BtDriver<'static>, 10>,
+    spawner: Spawner,
+    mac_addr: [u8; 6],
+    mut rng: Trng<'static, TRNG>,
+) {
+    let address: Address = Address::random(mac_addr);
+    info!("Our address = {:?}", address);
+
+    let resources = {
+        static RESOURCE: StaticCell<BleHostResource> = StaticCell::new();
+        RESOURCE.init(HostResources::new())
+    };
+    let stack: &'static Stack<'static, Controller, DefaultPacketPool> = {
+        static STACK: StaticCell<
+            Stack<'_, ExternalController<BtDriver<'static>, 10>, DefaultPacketPool>,
+        > = StaticCell::new();
+        STACK.init(
+            trouble_host::new(controller, resources)
+                .set_random_address(address)
+                .set_random_generator_seed(&mut rng),
+        )
+    };
+    access::load(stack).await;
+    let Host {
+        mut peripheral,
+        runner,
+        ..
+    } = stack.build();
+    spawner.spawn(host_task(runner).unwrap());
+
+    info!("Starting advertising and GATT service");
+    let settings = settings::get().await;
+    // The GAP device name can't change while the server exists, a new name is used for
+    // advertising right away but only becomes the GAP name after a restart.
+    let name: &'static String<NAME_MAX_LEN> = {
+        static NAME: StaticCell<String<NAME_MAX_LEN>> = StaticCell::new();
+        NAME.init(settings.name.clone())
+    };
+    let server: &'static Server<'static> =
+        match Server::new_with_config(GapConfig::Peripheral(PeripheralConfig {
+            name: name.as_str(),
+            appearance: &appearance::DISPLAY,
+        })) {
+            Ok(server) => {
+                static SERVER: StaticCell<Server<'static>> = StaticCell::new();
+                SERVER.init(server)
+            }
+            Err(e) => {
+                error!("[gatt] failed to create server: {:?}", e);
+                return;
+            }
+        };
+
+    if let Err(e) = store_settings(server, &settings) {
+        warn!("[gatt] error storing settings: {:?}", e);
+    }
+    store_slot_table(server).await;
+    if let Err(e) = server
+        .dashboard_service
+        .schedule
+        .set(server, &schedule::encoded().await)
+    {
+        warn!("[gatt] error storing schedule: {:?}", e);
+    }
+    if let Err(e) = server
+        .current_time_service
+        .local_time_information
+        .set(server, &clock::local_time_information())
+    {
+        warn!("[gatt] error storing local time information: {:?}", e);
+    }
+
+    loop {
+        // only advertise while another central can connect
+        while ACTIVE_CONNECTIONS.load(Ordering::Relaxed) >= CONNECTIONS_MAX {
+            CONNECTION_CLOSED.wait().await;
+        }
+
+        let settings = settings::get().await;
+        match advertise(&mut peripheral, server, &settings).await {
+            Ok(conn) => match connection_task(stack, server, conn) {
+                Ok(token) => {
+                    ACTIVE_CONNECTIONS.fetch_add(1, Ordering::Relaxed);
+                    spawner.spawn(token);
+                }
+                Err(e) => warn!("[gatt] error spawning connection task: {:?}", e),
+            },
+            Err(e) => {
+                let e = defmt::Debug2Format(&e);
+                warn!("[adv] error: {:?}", e);
+                Timer::after_millis(5000).await;
+            }
+        }
+    }
+}
+
+/// Create an advertiser to use to connect to a BLE Central, and wait for it to connect.
+///
+/// Advertises with the fast interval first and falls back to the slow interval once
+/// no central has connected within the configured timeout.
+async fn advertise<'values, 'server>(
+    peripheral: &mut Peripheral<'values, Controller, DefaultPacketPool>,
+    server: &'server Server<'values>,
+    settings: &Settings,
+) -> Result<
+    GattConnection<'values, 'server, DefaultPacketPool>,
+    BleHostError<cyw43::bluetooth::Error>,
+> {
+    // Legacy advertising PDUs carry at most 31 bytes
+    const GAP_ADV_LIMIT: usize = 31;
+    let config = &settings.advertising;
+    let manufacturer_data = advertising::manufacturer_data(&device_state());
+    let mut advertiser_data = [0; GAP_ADV_LIMIT];
+    let ad_len = AdStructure::encode_slice(
+        &[
+            AdStructure::Flags(LE_GENERAL_DISCOVERABLE | BR_EDR_NOT_SUPPORTED),
+            AdStructure::ManufacturerSpecificData {
+                company_identifier: advertising::COMPANY_ID,
+                payload: &manufacturer_data,
+            },
+        ],
+        &mut advertiser_data[..],
+    )?;
+    let mut scan_data = [0; GAP_ADV_LIMIT];
+    let scan_len = AdStructure::encode_slice(
+        &[
+            advertising::local_name(&settings.name),
+            // a second 128 bit UUID doesn't fit next to the name, the hub finds the
+            // settings service once connected
+            AdStructure::ServiceUuids128(&[DASHBOARD_UUID]),
+        ],
+        &mut scan_data[..],
+    )?;
+    let advertisement = || Advertisement::ConnectableScannableUndirected {
+        adv_data: &advertiser_data[0..ad_len],
+        scan_data: &scan_data[0..scan_len],
+    };
+
+    let advertiser = peripheral
+        .advertise(
+            &advertising::parameters(config.fast_interval),
+            advertisement(),
+        )
+        .await?;
+    info!("[adv] advertising");
+    let conn = match select(advertiser.accept(), Timer::after(config.fast_timeout)).await {
+        Either::First(conn) => conn?,
+        Either::Second(()) => loop {
+            let advertiser = peripheral
+                .advertise(
+                    &advertising::parameters(config.slow_interval),
+                    advertisement(),
+                )
+                .await?;
+            info!("[adv] advertising with slow interval");
+            let Some(beacon_period) = config.beacon_period else {
+                break advertiser.accept().await?;
+            };
+            // interleave the connectable advertisement with status beacons
+            match select(advertiser.accept(), Timer::after(beacon_period)).await {
+                Either::First(conn) => break conn?,
+                Either::Second(()) => broadcast_beacon(peripheral).await?,
+            }
+        },
+    };
+    let conn = conn.with_attribute_server(server)?;
+    info!("[adv] connection established");
+    Ok(conn)
+}
+
+/// Expose the persisted settings through the settings characteristics.
+fn store_settings(server: &Server<'_>, settings: &Settings) -> Result<(), Error> {
+    let service = &server.settings_service;
+    service
+        .advertising
+        .set(server, &settings.advertising.encode())?;
+    service
+        .name
+        .set(server, &settings::pad_text(&settings.name))?;
+    service
+        .label
+        .set(server, &settings::pad_text(&settings.label))?;
+    service
+        .slideshow
+        .set(server, &settings.slideshow.encode())?;
+    service.widget.set(server, &settings.widget.encode())?;
+    service
+        .orientation
+        .set(server, &settings.orientation.encode())?;
+    service
+        .refresh_policy
+        .set(server, &settings.refresh.encode())?;
+    Ok(())
+}
+
+/// Expose the stored images through the slots characteristic.
+async fn store_slot_table(server: &Server<'_>) {
+    if let Err(e) = server
+        .dashboard_service
+        .slots
+        .set(server, &slots::table().await)
+    {
+        warn!("[gatt] error storing slot table: {:?}", e);
+    }
+}
+
+/// Broadcast a non-connectable status beacon for [`advertising::BEACON_DURATION`].
+async fn broadcast_beacon(
+    peripheral: &mut Peripheral<'_, Controller, DefaultPacketPool>,
+) -> Result<(), BleHostError<cyw43::bluetooth::Error>> {
+    let beacon_data = advertising::beacon_data(&device_state());
+    let mut advertiser_data = [0; 31];
+    let ad_len = AdStructure::encode_slice(
+        &[
+            AdStructure::Flags(BR_EDR_NOT_SUPPORTED),
+            AdStructure::ManufacturerSpecificData {
+                company_identifier: advertising::COMPANY_ID,
+                payload: &beacon_data,
+            },
+        ],
+        &mut advertiser_data[..],
+    )?;
+    let _advertiser = peripheral
+        .advertise(
+            &advertising::parameters(advertising::BEACON_INTERVAL),
+            Advertisement::NonconnectableNonscannableUndirected {
+                adv_data: &advertiser_data[0..ad_len],
+            },
+        )
+        .await?;
+    info!("[adv] broadcasting status beacon");
+    Timer::after(advertising::BEACON_DURATION).await;
+    Ok(())
+}
+
+/// Request the largest link layer packets, so long writes aren't fragmented.
+async fn update_data_length(
+    stack: &Stack<'_, Controller, DefaultPacketPool>,
+    conn: &GattConnection<'_, '_, DefaultPacketPool>,
+) {
+    info!("[gatt] ATT MTU: {}", conn.raw().att_mtu());
+    if let Err(e) = conn.raw().update_data_length(stack, 251, 2120).await {
+        let e = defmt::Debug2Format(&e);
+        warn!("[gatt] error updating data length: {:?}", e);
+    }
+}
+
+async fn gatt_events_task<P: PacketPool>(
+    server: &Server<'_>,
+    conn: &GattConnection<'_, '_, P>,
+    activity: &Activity,
+) -> Result<(), Error> {
+    let mut transfer = Transfer::new();
+    // parameter updates are only reported once the link changes, so start from the
+    // parameters the connection was established with
+    let mut link = LinkInfo::new(conn.raw().params());
+    report_link(server, &link);
+    report_status(server, conn, TransferStatus::Idle).await;
+    let reason = loop {
+        match conn.next().await {
+            GattConnectionEvent::Disconnected { reason } => break reason,
+            GattConnectionEvent::ConnectionParamsUpdated {
+                conn_interval,
+                peripheral_latency,
+                supervision_timeout,
+            } => {
+                link.set_params(conn_interval, peripheral_latency, supervision_timeout);
+                report_link(server, &link);
+            }
+            GattConnectionEvent::PhyUpdated { tx_phy, rx_phy } => {
+                link.set_phy(tx_phy, rx_phy);
+                report_link(server, &link);
+            }
+            GattConnectionEvent::PairingComplete {
+                security_level,
+                bond,
+            } => {
+                info!("[access] pairing complete: {:?}", security_level);
+                if let Some(bond) = bond
+                    && let Err(e) = access::add_bond(bond).await
+                {
+                    warn!("[access] error storing bond: {:?}", e);
+                }
+            }
+            GattConnectionEvent::PairingFailed(e) => {
+                warn!("[access] pairing failed: {:?}", e);
+            }
+            GattConnectionEvent::Gatt { event } => {
+                let result = match &event {
+                    GattEvent::Read(event) => {
+                        if event.handle() == server.dashboard_service.cursor.handle {
+                            let value = server.get(&server.dashboard_service.cursor);
+                            info!("[gatt] Read Event to Cursor Characteristic: {:?}", value);
+                        } else if event.handle() == server.current_time_service.current_time.handle
+                        {
+                            // refresh the time right before the response is sent
+                            let time = clock::current_time(0);
+                            if let Err(e) =
+                                server.current_time_service.current_time.set(server, &time)
+                            {
+                                warn!("[gatt] error storing current time: {:?}", e);
+                            }
+                        } else if event.handle() == server.dashboard_service.refresh_stats.handle {
+                            let stats = refresh::stats();
+                            if let Err(e) =
+                                server.dashboard_service.refresh_stats.set(server, &stats)
+                            {
+                                warn!("[gatt] error storing refresh stats: {:?}", e);
+                            }
+                        } else if event.handle() == server.dashboard_service.image_hash.handle {
+                            let hash = device_state().image_hash;
+                            if let Err(e) = server.dashboard_service.image_hash.set(server, &hash) {
+                                warn!("[gatt] error storing image hash: {:?}", e);
+                            }
+                        } else if event.handle() == server.dashboard_service.temperature.handle {
+                            let temperature = temperature::current().unwrap_or(TEMPERATURE_UNKNOWN);
+                            if let Err(e) = server
+                                .dashboard_service
+                                .temperature
+                                .set(server, &temperature)
+                            {
+                                warn!("[gatt] error storing temperature: {:?}", e);
+                            }
+                        }
+                        Ok(())
+                    }
+                    GattEvent::Write(event) => {
+                        activity.signal(());
+                        handle_write(server, conn, &mut transfer, event).await
+                    }
+                    _ => Ok(()),
+                };
+                // This step is also performed at drop(), but writing it explicitly is necessary
+                // in order to ensure reply is sent.
+                let reply = match result {
+                    Ok(()) => event.accept(),
+                    Err(ref e) => {
+                        warn!("[gatt] rejecting request: {:?}", e);
+                        event.reject(e.att_error())
+                    }
+                };
+                match reply {
+                    Ok(reply) => reply.send().await,
+                    Err(e) => warn!("[gatt] error sending response: {:?}", e),
+                };
+                if let Err(e) = &result {
+                    report_status(server, conn, e.into()).await;
+                }
+            }
+            _ => {} // ignore other Gatt Connection Events
+        }
+    };
+    info!("[gatt] disconnected: {:?}", reason);
+    Ok(())
+}
+
+/// Apply a write request to the display, returning an error instead of panicking
+/// so it can be reported back to the hub.
+async fn handle_write<P: PacketPool>(
+    server: &Server<'_>,
+    conn: &GattConnection<'_, '_, P>,
+    transfer: &mut Transfer,
+    event: &WriteEvent<'_, '_, P>,
+) -> Result<(), FirmwareError> {
+    // while another central uploads an image, this connection only has read access
+    let handle = conn.raw().handle();
+    if !may_write(handle) {
+        return Err(FirmwareError::UploadBusy);
+    }
+    access::authorize(conn.raw(), required_roles(server, event.handle())).await?;
+
+    if event.handle() == server.dashboard_service.write_buffer.handle {
+        claim_upload(handle)?;
+        // a new transfer starts at the first chunk, even if an aborted one left the
+        // cursor behind
+        let cursor = if transfer.is_empty() {
+            0
+        } else {
+            server.get(&server.dashboard_service.cursor)?
+        };
+        let written = write_chunks(transfer, cursor, event.data()).await?;
+        server
+            .dashboard_service
+            .cursor
+            .set(server, &(cursor + written))?;
+        report_progress(server, conn, transfer, written).await?;
+    } else if event.handle() == server.dashboard_service.stream.handle {
+        claim_upload(handle)?;
+        let (index, chunks) = split_stream(event.data())?;
+        let written = write_chunks(transfer, index, chunks).await?;
+        report_progress(server, conn, transfer, written).await?;
+        if transfer.ack_due() {
+            report_ack(server, conn, transfer).await;
+        }
+    } else if event.handle() == server.dashboard_service.write.handle {
+        if event.data().first().is_some_and(|&commit| commit != 0) {
+            if !transfer.is_complete() {
+                report_ack(server, conn, transfer).await;
+                return Err(FirmwareError::IncompleteTransfer);
+            }
+
+            server.dashboard_service.cursor.set(server, &0u32)?;
+            transfer.reset();
+
+            let result = commit_frame(server, conn).await;
+            slots::end_upload(handle).await;
+            result?;
+        }
+    } else if event.handle() == server.settings_service.advertising.handle {
+        let config = AdvertisingConfig::decode(event.data())?;
+        settings::update(|settings| settings.advertising = config).await?;
+    } else if event.handle() == server.settings_service.name.handle {
+        let name = settings::parse_text(event.data())?;
+        if name.is_empty() {
+            return Err(FirmwareError::InvalidValue);
+        }
+        settings::update(|settings| settings.name = name).await?;
+    } else if event.handle() == server.settings_service.label.handle {
+        let label = settings::parse_text(event.data())?;
+        settings::update(|settings| settings.label = label).await?;
+    } else if event.handle() == server.dashboard_service.layout.handle {
+        claim_upload(handle)?;
+        if layout::receive(event.data()).await? {
+            report_status(server, conn, TransferStatus::Decoding).await;
+            let result = match render_layout().await {
+                Ok(()) => commit_frame(server, conn).await,
+                Err(e) => Err(e),
+            };
+            slots::end_upload(handle).await;
+            result?;
+        }
+    } else if event.handle() == server.dashboard_service.font.handle {
+        fonts::receive(event.data()).await?;
+    } else if event.handle() == server.dashboard_service.slot_control.handle {
+        let result = slots::control(event.data()).await;
+        store_slot_table(server).await;
+        result?;
+    } else if event.handle() == server.dashboard_service.schedule.handle {
+        schedule::set(event.data()).await?;
+    } else if event.handle() == server.dashboard_service.time.handle {
+        clock::sync_from(event.data()).await?;
+        time_changed(server, conn, CurrentTime::ADJUST_EXTERNAL_REFERENCE).await;
+    } else if event.handle() == server.current_time_service.current_time.handle {
+        clock::sync_from_current_time(event.data()).await?;
+        time_changed(server, conn, CurrentTime::ADJUST_MANUAL).await;
+    } else if event.handle() == server.current_time_service.local_time_information.handle {
+        clock::set_local_time_information(event.data()).await?;
+        time_changed(server, conn, CurrentTime::ADJUST_TIME_ZONE).await;
+    } else if event.handle() == server.settings_service.widget.handle {
+        let config = WidgetConfig::decode(event.data())?;
+        settings::update(|settings| settings.widget = config).await?;
+        WIDGET_CHANGED.signal(());
+    } else if event.handle() == server.settings_service.orientation.handle {
+        let config = OrientationConfig::decode(event.data())?;
+        settings::update(|settings| settings.orientation = config).await?;
+        if let Some(display) = crate::display::DISPLAY.lock().await.as_mut() {
+            display.frame_mut().set_orientation(config);
+        }
+    } else if event.handle() == server.settings_service.refresh_policy.handle {
+        let policy = RefreshPolicy::decode(event.data())?;
+        settings::update(|settings| settings.refresh = policy).await?;
+    } else if event.handle() == server.settings_service.slideshow.handle {
+        let config = SlideshowConfig::decode(event.data())?;
+        settings::update(|settings| settings.slideshow = config).await?;
+        SLIDESHOW_CHANGED.signal(());
+    } else if event.handle() == server.settings_service.roles.handle {
+        access::assign_roles(event.data()).await?;
+    }
+    Ok(())
+}
+
+/// Let the tasks depending on the time know that the clock changed, and notify the hub
+/// about the new time as the Current Time Service requires.
+async fn time_changed<P: PacketPool>(
+    server: &Server<'_>,
+    conn: &GattConnection<'_, '_, P>,
+    adjust_reason: u8,
+) {
+    SCHEDULE_CHANGED.signal(());
+    WIDGET_CHANGED.signal(());
+    if let Err(e) = server
+        .current_time_service
+        .current_time
+        .notify(conn, &clock::current_time(adjust_reason))
+        .await
+    {
+        warn!("[gatt] error notifying current time: {:?}", e);
+    }
+}
+
+/// Roles a central needs to write the characteristic at `handle`.
+fn required_roles(server: &Server<'_>, handle: u16) -> Roles {
+    let dashboard = &server.dashboard_service;
+    if [
+        dashboard.write_buffer.handle,
+        dashboard.stream.handle,
+        dashboard.write.handle,
+        dashboard.layout.handle,
+        dashboard.font.handle,
+        dashboard.slot_control.handle,
+        dashboard.schedule.handle,
+        dashboard.time.handle,
+        server.current_time_service.current_time.handle,
+        server.current_time_service.local_time_information.handle,
+    ]
+    .contains(&handle)
+    {
+        Roles::HUB
+    } else {
+        Roles::ADMIN
+    }
+}
+
+/// Show the received frame on the display, or store it if a slot has been selected.
+pub(super) async fn commit_frame<P: PacketPool>(
+    server: &Server<'_>,
+    conn: &GattConnection<'_, '_, P>,
+) -> Result<(), FirmwareError> {
+    let mut guard = crate::display::DISPLAY.lock().await;
+    let display = guard.as_mut().ok_or(FirmwareError::DisplayUnavailable)?;
+    update_device_state(|state| state.error_flags &= !ERROR_TRANSFER_FAILED);
+
+    let mut status = TransferStatus::Done;
+    if let Some((index, name)) = slots::take_target().await {
+        let result = slots::store(index, name, display.frame().buffer()).await;
+        // the panel keeps showing the frame from before the upload
+        slots::restore_shown(display).await?;
+        result?;
+        store_slot_table(server).await;
+    } else {
+        report_status(server, conn, TransferStatus::Refreshing).await;
+        let image_hash = display.frame().hash();
+        widget::overlay(display).await;
+        let result = display.display_buffer();
+        record_refresh(image_hash, result.is_ok());
+        if result? == Refresh::Unchanged {
+            status = TransferStatus::Unchanged;
+        }
+    }
+    report_status(server, conn, status).await;
+    Ok(())
+}
+
+/// Render the received layout into the display buffer.
+async fn render_layout() -> Result<(), FirmwareError> {
+    let mut guard = crate::display::DISPLAY.lock().await;
+    let display = guard.as_mut().ok_or(FirmwareError::DisplayUnavailable)?;
+    slots::park_shown(display).await?;
+    layout::render_received(&mut display.frame_mut().canvas()).await
+}
+
+/// Write consecutive chunks starting at chunk `index` into the display buffer,
+/// returning the number of chunks written.
+async fn write_chunks(
+    transfer: &mut Transfer,
+    index: u32,
+    data: &[u8],
+) -> Result<u32, FirmwareError> {
+    let (chunks, remainder) = data.as_chunks::<CHUNK_LEN>();
+    if chunks.is_empty() || !remainder.is_empty() {
+        return Err(FirmwareError::InvalidLength);
+    }
+
+    let mut guard = crate::display::DISPLAY.lock().await;
+    let display = guard.as_mut().ok_or(FirmwareError::DisplayUnavailable)?;
+    slots::park_shown(display).await?;
+
+    let mut written = 0;
+    for chunk in chunks {
+        display
+            .frame_mut()
+            .write_to_buffer(chunk, index + written)?;
+        transfer.mark(index + written);
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// Update the received byte count, notifying the hub every [`PROGRESS_INTERVAL`] bytes.
+async fn report_progress<P: PacketPool>(
+    server: &Server<'_>,
+    conn: &GattConnection<'_, '_, P>,
+    transfer: &Transfer,
+    written: u32,
+) -> Result<(), FirmwareError> {
+    let received = transfer.received_bytes();
+    let before = received.saturating_sub(written * CHUNK_LEN as u32);
+    let status = TransferStatus::Receiving { bytes: received };
+    if before / PROGRESS_INTERVAL != received / PROGRESS_INTERVAL {
+        report_status(server, conn, status).await;
+    } else {
+        server
+            .dashboard_service
+            .status
+            .set(server, &status.encode())?;
+    }
+    Ok(())
+}
+
+/// Notify the hub which chunks have been received, so it can resend the missing ones.
+async fn report_ack<P: PacketPool>(
+    server: &Server<'_>,
+    conn: &GattConnection<'_, '_, P>,
+    transfer: &Transfer,
+) {
+    if let Err(e) = server
+        .dashboard_service
+        .ack
+        .notify(conn, &transfer.ack().encode())
+        .await
+    {
+        warn!("[gatt] error notifying ack: {:?}", e);
+    }
+}
+
+/// Store the negotiated link parameters, so they can be read for diagnostics.
+fn report_link(server: &Server<'_>, link: &LinkInfo) {
+    info!("[link] parameters: {:?}", link);
+    if let Err(e) = server.dashboard_service.link.set(server, &link.encode()) {
+        warn!("[link] error storing parameters: {:?}", e);
+    }
+}
+
+/// Update the status characteristic and notify the hub about the change.
+pub(super) async fn report_status<P: PacketPool>(
+    server: &Server<'_>,
+    conn: &GattConnection<'_, '_, P>,
+    status: TransferStatus,
+) {
+    info!("[gatt] status: {:?}", status);
+    if let TransferStatus::Error { .. } = status {
+        update_device_state(|state| state.error_flags |= ERROR_TRANSFER_FAILED);
+    }
+    if let Err(e) = server
+        .dashboard_service
+        .status
+        .notify(conn, &status.encode())
+        .await
+    {
+        warn!("[gatt] error notifying status: {:?}", e);
+    }
+}
@@ -0,0 +1,108 @@
+use periphery_protocol::gatt::{dashboard, settings};
+use periphery_protocol::status::TransferStatus;
+use periphery_protocol::time::{CurrentTime, LocalTimeInformation, TimeSync};
+use periphery_protocol::transfer::{Ack, L2CAP_PSM, MAX_WRITE_LEN};
+use trouble_host::prelude::*;
+
+use crate::bluetooth::access::RoleAssignment;
+use crate::bluetooth::advertising::AdvertisingConfig;
+use crate::bluetooth::link::LinkInfo;
+use crate::display::OrientationConfig;
+use crate::refresh::{REFRESH_STATS_LEN, RefreshPolicy};
+use crate::schedule::SCHEDULE_LEN;
+use crate::settings::{LABEL_MAX_LEN, NAME_MAX_LEN};
+use crate::slots::{SLOT_CONTROL_LEN, SLOT_TABLE_LEN, SlideshowConfig};
+use crate::widget::WidgetConfig;
+
+pub const DASHBOARD_UUID: [u8; 16] = BluetoothUuid128::new(dashboard::SERVICE).to_le_bytes();
+
+#[gatt_server]
+pub struct Server {
+    pub dashboard_service: DashboardService,
+    pub settings_service: SettingsService,
+    pub current_time_service: CurrentTimeService,
+}
+
+#[gatt_service(uuid = dashboard::SERVICE)]
+pub struct DashboardService {
+    #[characteristic(uuid = dashboard::WRITE_BUFFER, write, value = [0; MAX_WRITE_LEN])]
+    pub write_buffer: [u8; MAX_WRITE_LEN],
+    #[characteristic(uuid = dashboard::WRITE, write)]
+    pub write: bool,
+    #[characteristic(uuid = dashboard::CURSOR, read)]
+    pub cursor: u32,
+    #[characteristic(uuid = dashboard::STATUS, read, notify)]
+    pub status: [u8; TransferStatus::ENCODED_LEN],
+    #[characteristic(uuid = dashboard::STREAM, write_without_response, value = [0; MAX_WRITE_LEN])]
+    pub stream: [u8; MAX_WRITE_LEN],
+    #[characteristic(uuid = dashboard::ACK, notify)]
+    pub ack: [u8; Ack::ENCODED_LEN],
+    #[characteristic(uuid = dashboard::L2CAP_PSM, read, value = L2CAP_PSM)]
+    pub l2cap_psm: u16,
+    #[characteristic(uuid = dashboard::LINK, read)]
+    pub link: [u8; LinkInfo::ENCODED_LEN],
+    /// Selects the slot the next frame is stored into, shows or deletes stored images
+    #[characteristic(uuid = dashboard::SLOT_CONTROL, write)]
+    pub slot_control: [u8; SLOT_CONTROL_LEN],
+    #[characteristic(uuid = dashboard::SLOTS, read, value = [0; SLOT_TABLE_LEN])]
+    pub slots: [u8; SLOT_TABLE_LEN],
+    /// Time ranges in which stored images are shown, see [`crate::schedule`]
+    #[characteristic(uuid = dashboard::SCHEDULE, write, read, value = [0; SCHEDULE_LEN])]
+    pub schedule: [u8; SCHEDULE_LEN],
+    /// Current time, used to evaluate the schedule
+    #[characteristic(uuid = dashboard::TIME, write)]
+    pub time: [u8; TimeSync::ENCODED_LEN],
+    /// Drawing commands rendered on the device instead of a bitmap, see [`crate::layout`]
+    #[characteristic(uuid = dashboard::LAYOUT, write, value = [0; MAX_WRITE_LEN])]
+    pub layout: [u8; MAX_WRITE_LEN],
+    /// Bitmap fonts for layout text, written in pieces, see [`crate::fonts`]
+    #[characteristic(uuid = dashboard::FONT, write, value = [0; MAX_WRITE_LEN])]
+    pub font: [u8; MAX_WRITE_LEN],
+    /// Refresh counters, see [`crate::refresh::stats`]
+    #[characteristic(uuid = dashboard::REFRESH_STATS, read)]
+    pub refresh_stats: [u8; REFRESH_STATS_LEN],
+    /// FNV-1a hash of the last image shown, before overlays, so the hub can skip uploading
+    /// an image that is already displayed
+    #[characteristic(uuid = dashboard::IMAGE_HASH, read)]
+    pub image_hash: u32,
+    /// Temperature next to the panel in 0.01 °C, -32768 if unknown
+    #[characteristic(uuid = dashboard::TEMPERATURE, read)]
+    pub temperature: i16,
+}
+
+#[gatt_service(uuid = settings::SERVICE)]
+pub struct SettingsService {
+    #[characteristic(uuid = settings::STATUS, write, read)]
+    pub status: bool,
+    #[characteristic(uuid = settings::ADVERTISING, write, read)]
+    pub advertising: [u8; AdvertisingConfig::ENCODED_LEN],
+    /// Used for advertising right away and as GAP device name after a restart
+    #[characteristic(uuid = settings::NAME, write, read)]
+    pub name: [u8; NAME_MAX_LEN],
+    #[characteristic(uuid = settings::LABEL, write, read)]
+    pub label: [u8; LABEL_MAX_LEN],
+    /// Assigns roles to a bonded central, see [`crate::bluetooth::access::assign_roles`]
+    #[characteristic(uuid = settings::ROLES, write)]
+    pub roles: [u8; RoleAssignment::ENCODED_LEN],
+    #[characteristic(uuid = settings::SLIDESHOW, write, read)]
+    pub slideshow: [u8; SlideshowConfig::ENCODED_LEN],
+    /// Clock and date drawn on top of the images
+    #[characteristic(uuid = settings::WIDGET, write, read)]
+    pub widget: [u8; WidgetConfig::ENCODED_LEN],
+    /// Rotation and mirroring of the mounted panel, applies to images received afterwards
+    #[characteristic(uuid = settings::ORIENTATION, write, read)]
+    pub orientation: [u8; OrientationConfig::ENCODED_LEN],
+    /// When the panel is cleaned to remove ghosting
+    #[characteristic(uuid = settings::REFRESH_POLICY, write, read)]
+    pub refresh_policy: [u8; RefreshPolicy::ENCODED_LEN],
+}
+
+/// Bluetooth Current Time Service, so hubs can sync the clock with standard tooling
+#[gatt_service(uuid = service::CURRENT_TIME)]
+pub struct CurrentTimeService {
+    /// Local time, writable so the clock can be synced
+    #[characteristic(uuid = characteristic::CURRENT_TIME, read, write, notify)]
+    pub current_time: [u8; CurrentTime::ENCODED_LEN],
+    #[characteristic(uuid = characteristic::LOCAL_TIME_INFORMATION, read, write)]
+    pub local_time_information: [u8; LocalTimeInformation::ENCODED_LEN],
+}
@@ -0,0 +1,127 @@
+use core::cell::Cell;
+
+use bt_hci::param::ConnHandle;
+use embassy_sync::blocking_mutex::{Mutex, raw::CriticalSectionRawMutex};
+use periphery_protocol::transfer::{ACK_INTERVAL, Ack};
+
+use crate::display::{CHUNK_COUNT, CHUNK_LEN};
+use crate::error::Error;
+
+const BITMAP_WORDS: usize = (CHUNK_COUNT as usize).div_ceil(32);
+
+/// Connection currently owning the upload session, only one central may upload at a time
+static UPLOAD_OWNER: Mutex<CriticalSectionRawMutex, Cell<Option<ConnHandle>>> =
+    Mutex::new(Cell::new(None));
+
+/// Claim the upload session for `conn`, unless another connection already owns it.
+pub fn claim_upload(conn: ConnHandle) -> Result<(), Error> {
+    UPLOAD_OWNER.lock(|owner| match owner.get() {
+        Some(current) if current != conn => Err(Error::UploadBusy),
+        _ => {
+            owner.set(Some(conn));
+            Ok(())
+        }
+    })
+}
+
+/// Release the upload session, if it is owned by `conn`, returning whether it was.
+pub fn release_upload(conn: ConnHandle) -> bool {
+    UPLOAD_OWNER.lock(|owner| {
+        let owned = owner.get() == Some(conn);
+        if owned {
+            owner.set(None);
+        }
+        owned
+    })
+}
+
+/// Whether `conn` may write, which is the case unless another connection is uploading.
+pub fn may_write(conn: ConnHandle) -> bool {
+    UPLOAD_OWNER.lock(|owner| owner.get().is_none_or(|current| current == conn))
+}
+
+/// Whether any connection is currently uploading into the display buffer.
+pub fn upload_active() -> bool {
+    UPLOAD_OWNER.lock(|owner| owner.get().is_some())
+}
+
+/// Keeps track of the chunks received during an upload, so streamed writes can be
+/// acknowledged and gaps are detected before the frame gets committed.
+pub struct Transfer {
+    received: [u32; BITMAP_WORDS],
+    /// One past the highest chunk index received so far
+    end: u32,
+    count: u32,
+    unacknowledged: u32,
+}
+
+impl Transfer {
+    pub const fn new() -> Self {
+        Transfer {
+            received: [0; BITMAP_WORDS],
+            end: 0,
+            count: 0,
+            unacknowledged: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Record that the chunk at `index` has been written to the display buffer.
+    pub fn mark(&mut self, index: u32) {
+        if !self.is_received(index) {
+            self.received[(index / 32) as usize] |= 1 << (index % 32);
+            self.count += 1;
+        }
+        self.end = self.end.max(index + 1);
+    }
+
+    pub fn is_received(&self, index: u32) -> bool {
+        index < CHUNK_COUNT && self.received[(index / 32) as usize] & (1 << (index % 32)) != 0
+    }
+
+    /// Index of the first chunk that has not been received yet.
+    pub fn first_missing(&self) -> u32 {
+        (0..CHUNK_COUNT)
+            .find(|&index| !self.is_received(index))
+            .unwrap_or(CHUNK_COUNT)
+    }
+
+    /// Whether every chunk up to the highest one written has been received.
+    pub fn is_complete(&self) -> bool {
+        self.first_missing() >= self.end
+    }
+
+    /// Whether no chunk has been received since the transfer started.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn received_bytes(&self) -> u32 {
+        self.count * CHUNK_LEN as u32
+    }
+
+    /// Count a streamed write, returning true once an acknowledgement is due.
+    pub fn ack_due(&mut self) -> bool {
+        self.unacknowledged += 1;
+        if self.unacknowledged >= ACK_INTERVAL {
+            self.unacknowledged = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn ack(&self) -> Ack {
+        let first_missing = self.first_missing();
+        let received = (0..32)
+            .filter(|&offset| self.is_received(first_missing + offset))
+            .fold(0u32, |bitmap, offset| bitmap | 1 << offset);
+        Ack {
+            first_missing,
+            received,
+        }
+    }
+}
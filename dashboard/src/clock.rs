@@ -0,0 +1,200 @@
+use core::cell::Cell;
+
+use defmt::{info, warn};
+use embassy_sync::blocking_mutex::{Mutex, raw::CriticalSectionRawMutex};
+use periphery_protocol::time::{CurrentTime, LocalTimeInformation, TimeSync, UTC_OFFSET_RANGE};
+
+use crate::error::Error;
+use crate::rtc;
+use crate::settings;
+
+pub use periphery_protocol::time::LocalTime;
+
+/// Earlier timer values can't be a valid time, so the timer hasn't been set yet
+const MIN_VALID_MILLIS: u64 = 1_704_067_200_000;
+/// The drift is only estimated once enough time passed for it to be measurable
+const MIN_DRIFT_INTERVAL_MS: u64 = 60 * 60 * 1000;
+/// Larger drifts indicate a bogus time sync rather than an inaccurate timer
+const MAX_DRIFT_PPM: i32 = 500;
+
+static CLOCK: Mutex<CriticalSectionRawMutex, Cell<Clock>> = Mutex::new(Cell::new(Clock {
+    synced_at: None,
+    config: ClockConfig {
+        utc_offset: 0,
+        drift_ppm: 0,
+    },
+}));
+
+/// Wall-clock time kept by the always-on timer, see [`rtc::start`].
+#[derive(Clone, Copy)]
+struct Clock {
+    /// Timer value at the last sync, the drift is corrected from there on
+    synced_at: Option<u64>,
+    config: ClockConfig,
+}
+
+/// Clock settings persisted with the settings, so they survive losing power.
+#[derive(Clone, Copy, Default, PartialEq, Eq, defmt::Format)]
+pub struct ClockConfig {
+    /// Offset of the local time zone to UTC in minutes
+    pub utc_offset: i16,
+    /// Measured deviation of the timer, positive if it runs fast
+    pub drift_ppm: i32,
+}
+
+impl ClockConfig {
+    /// Encoded as `[UTC offset (i16, minutes), drift (i32, ppm)]`, little endian.
+    pub const ENCODED_LEN: usize = 6;
+
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut encoded = [0u8; Self::ENCODED_LEN];
+        encoded[0..2].copy_from_slice(&self.utc_offset.to_le_bytes());
+        encoded[2..6].copy_from_slice(&self.drift_ppm.to_le_bytes());
+        encoded
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let data: &[u8; Self::ENCODED_LEN] = data.try_into().map_err(|_| Error::InvalidLength)?;
+        let utc_offset = i16::from_le_bytes([data[0], data[1]]);
+        let drift_ppm = i32::from_le_bytes([data[2], data[3], data[4], data[5]]);
+        if !UTC_OFFSET_RANGE.contains(&utc_offset) || drift_ppm.abs() > MAX_DRIFT_PPM {
+            return Err(Error::InvalidValue);
+        }
+        Ok(ClockConfig {
+            utc_offset,
+            drift_ppm,
+        })
+    }
+}
+
+/// Start the timer and take over the time it kept across a reset, if any.
+pub fn init(config: ClockConfig) {
+    rtc::start();
+    let millis = rtc::millis();
+    let synced_at = (millis >= MIN_VALID_MILLIS).then_some(millis);
+    CLOCK.lock(|clock| clock.set(Clock { synced_at, config }));
+    match synced_at {
+        Some(_) => info!("[clock] kept time across reset: {}", local_time()),
+        None => info!("[clock] waiting for time sync"),
+    }
+}
+
+/// Milliseconds since the Unix epoch (UTC), if the clock has been set.
+pub fn now_millis() -> Option<u64> {
+    let clock = CLOCK.lock(|clock| clock.get());
+    let synced_at = clock.synced_at?;
+    let elapsed = rtc::millis().saturating_sub(synced_at);
+    let correction = elapsed as i64 * i64::from(clock.config.drift_ppm) / 1_000_000;
+    Some((synced_at + elapsed).saturating_add_signed(-correction))
+}
+
+/// Seconds since the Unix epoch (UTC), if the clock has been set.
+pub fn now() -> Option<u64> {
+    now_millis().map(|millis| millis / 1000)
+}
+
+pub fn local_time() -> Option<LocalTime> {
+    let utc_offset = CLOCK.lock(|clock| clock.get()).config.utc_offset;
+    LocalTime::from_unix(now()?, utc_offset)
+}
+
+/// Set the clock to `unix_millis`, measuring the drift of the timer since the last sync.
+pub async fn sync(unix_millis: u64, utc_offset: i16) {
+    let estimate = now_millis();
+    let config = CLOCK.lock(|clock| {
+        let mut value = clock.get();
+        if let (Some(synced_at), Some(estimate)) = (value.synced_at, estimate) {
+            let interval = unix_millis.saturating_sub(synced_at);
+            if interval >= MIN_DRIFT_INTERVAL_MS {
+                // the estimate already accounts for the drift measured so far
+                let error = estimate as i64 - unix_millis as i64;
+                let drift_ppm =
+                    i64::from(value.config.drift_ppm) + error * 1_000_000 / interval as i64;
+                value.config.drift_ppm =
+                    drift_ppm.clamp(-i64::from(MAX_DRIFT_PPM), MAX_DRIFT_PPM.into()) as i32;
+            }
+        }
+        value.synced_at = Some(unix_millis);
+        value.config.utc_offset = utc_offset;
+        clock.set(value);
+        value.config
+    });
+    rtc::set_millis(unix_millis);
+    info!(
+        "[clock] synced to {}, off by {} ms, drift {} ppm",
+        local_time(),
+        estimate.map(|estimate| estimate as i64 - unix_millis as i64),
+        config.drift_ppm
+    );
+    store(config).await;
+}
+
+/// Set the clock from a value written to the time characteristic.
+pub async fn sync_from(data: &[u8]) -> Result<(), Error> {
+    let time = TimeSync::decode(data)?;
+    sync(u64::from(time.unix) * 1000, time.utc_offset).await;
+    Ok(())
+}
+
+/// Set the clock from a value written to the Current Time characteristic.
+///
+/// The value is in local time, so the Local Time Information has to be written first.
+pub async fn sync_from_current_time(data: &[u8]) -> Result<(), Error> {
+    let current = CurrentTime::decode(data)?;
+    let utc_offset = CLOCK.lock(|clock| clock.get()).config.utc_offset;
+    let unix = current
+        .time
+        .to_unix(utc_offset)
+        .ok_or(Error::InvalidValue)?;
+    let fraction = u64::from(current.fraction) * 1000 / 256;
+    sync(unix * 1000 + fraction, utc_offset).await;
+    Ok(())
+}
+
+/// The local time as exposed by the Current Time characteristic, all zero if unknown.
+///
+/// `adjust_reason` combines the `CurrentTime::ADJUST_*` flags, it is only set when
+/// subscribers are notified about a change.
+pub fn current_time(adjust_reason: u8) -> [u8; CurrentTime::ENCODED_LEN] {
+    let (Some(time), Some(millis)) = (local_time(), now_millis()) else {
+        return [0; CurrentTime::ENCODED_LEN];
+    };
+    let fraction = ((millis % 1000) * 256 / 1000) as u8;
+    CurrentTime {
+        time,
+        fraction,
+        adjust_reason,
+    }
+    .encode()
+}
+
+/// Set the time zone from a value written to the Local Time Information characteristic.
+pub async fn set_local_time_information(data: &[u8]) -> Result<(), Error> {
+    let utc_offset = LocalTimeInformation::decode(data)?.utc_offset;
+
+    let config = CLOCK.lock(|clock| {
+        let mut value = clock.get();
+        value.config.utc_offset = utc_offset;
+        clock.set(value);
+        value.config
+    });
+    info!("[clock] UTC offset set to {} min", utc_offset);
+    store(config).await;
+    Ok(())
+}
+
+/// The time zone as exposed by the Local Time Information characteristic.
+pub fn local_time_information() -> [u8; LocalTimeInformation::ENCODED_LEN] {
+    let utc_offset = CLOCK.lock(|clock| clock.get()).config.utc_offset;
+    LocalTimeInformation { utc_offset }.encode()
+}
+
+/// Persist the clock settings, if they changed.
+async fn store(config: ClockConfig) {
+    if settings::get().await.clock == config {
+        return;
+    }
+    if let Err(e) = settings::update(|settings| settings.clock = config).await {
+        warn!("[clock] error storing clock settings: {:?}", e);
+    }
+}
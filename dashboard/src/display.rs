@@ -0,0 +1,107 @@
+use defmt::info;
+use embassy_rp::{
+    gpio::{Input, Output},
+    peripherals::SPI1,
+    spi::{Blocking, Spi},
+};
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::Mutex};
+use embassy_time::Delay;
+use embedded_graphics::primitives::Rectangle;
+use embedded_hal_bus::spi::{ExclusiveDevice, NoDelay};
+use epd_waveshare::{epd7in5b_v2::*, prelude::*};
+use periphery_render::screen::{Panel, Screen};
+
+pub use periphery_render::frame::{CHUNK_COUNT, CHUNK_LEN, FRAME_LEN, OrientationConfig};
+pub use periphery_render::screen::Refresh;
+
+use crate::error::Error;
+use crate::refresh;
+use crate::temperature;
+
+pub static DISPLAY: Mutex<CriticalSectionRawMutex, Option<Display>> = Mutex::new(None);
+
+/// Frame buffer shown on the e-ink panel.
+pub type Display<'a> = Screen<Epd<'a>>;
+
+type SpiDevice<'a> = ExclusiveDevice<Spi<'a, SPI1, Blocking>, Output<'a>, NoDelay>;
+
+/// The Waveshare 7.5" tri-color panel.
+pub struct Epd<'a> {
+    epd: Epd7in5<SpiDevice<'a>, Input<'a>, Output<'a>, Output<'a>, Delay>,
+    spi: SpiDevice<'a>,
+    sleeping: bool,
+}
+
+pub fn init<'a>(
+    mut spi: SpiDevice<'a>,
+    busy_in: Input<'a>,
+    dc: Output<'a>,
+    rst: Output<'a>,
+) -> Result<Display<'a>, Error> {
+    info!("setting up display");
+    // Setup EPD
+    let mut epd = Epd7in5::new(&mut spi, busy_in, dc, rst, &mut Delay, None)?;
+    epd.set_background_color(TriColor::White);
+
+    info!("epd created");
+
+    Ok(Screen::new(Epd {
+        epd,
+        spi,
+        sleeping: false,
+    }))
+}
+
+impl Epd<'_> {
+    /// Check the panel may be refreshed and count the refresh.
+    fn begin_refresh(&mut self) -> Result<(), Error> {
+        if !temperature::begin_refresh() {
+            return Err(Error::TemperatureOutOfRange);
+        }
+        refresh::count();
+        Ok(())
+    }
+}
+
+impl Panel for Epd<'_> {
+    type Error = Error;
+
+    fn show(&mut self, frame: &[u8]) -> Result<(), Error> {
+        self.begin_refresh()?;
+        self.epd
+            .update_and_display_frame(&mut self.spi, frame, &mut Delay)?;
+        self.epd.wait_until_idle(&mut self.spi, &mut Delay)?;
+        Ok(())
+    }
+
+    fn show_window(&mut self, window: &[u8], area: Rectangle) -> Result<(), Error> {
+        self.begin_refresh()?;
+        info!(
+            "[display] black changed in ({}, {}) {}x{}, refreshing partially",
+            area.top_left.x, area.top_left.y, area.size.width, area.size.height
+        );
+        self.epd.update_partial_frame2(
+            &mut self.spi,
+            window,
+            area.top_left.x as u32,
+            area.top_left.y as u32,
+            area.size.width,
+            area.size.height,
+            &mut Delay,
+        )?;
+        self.epd.wait_until_idle(&mut self.spi, &mut Delay)?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), Error> {
+        if self.sleeping {
+            self.epd.wake_up(&mut self.spi, &mut Delay)?;
+            self.sleeping = false;
+        }
+        // Clear e-paper display's buffer
+        self.epd.clear_frame(&mut self.spi, &mut Delay)?;
+        self.epd.wait_until_idle(&mut self.spi, &mut Delay)?;
+        info!("cleared Display");
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use core::convert::Infallible;
+
+use embassy_rp::{flash, spi};
+use embedded_hal_bus::spi::DeviceError;
+use periphery_protocol::status::TransferStatus;
+use trouble_host::prelude::AttErrorCode;
+
+/// Firmware wide error type, shared by the display, GATT and flash paths.
+///
+/// Errors are logged and reported back to the hub instead of panicking, so the
+/// device stays reachable even if a single transfer or refresh fails.
+#[derive(Debug, defmt::Format)]
+pub enum Error {
+    /// SPI communication with the e-ink display failed
+    Spi(spi::Error),
+    /// The display could not be initialized and is therefore not available
+    DisplayUnavailable,
+    /// A write targeted a position outside of the display buffer
+    CursorOutOfRange,
+    /// The written value has an invalid length
+    InvalidLength,
+    /// The frame was committed while chunks are still missing
+    IncompleteTransfer,
+    /// The written value is outside of the allowed range
+    InvalidValue,
+    /// Accessing a GATT attribute failed
+    Gatt(trouble_host::Error),
+    /// Reading or writing the onboard flash failed
+    Flash(flash::Error),
+    /// The persistent storage has not been initialized
+    StorageUnavailable,
+    /// Another connection currently owns the upload session
+    UploadBusy,
+    /// The central has to pair and bond before writing
+    Unauthenticated,
+    /// The central lacks the role required for the write
+    Unauthorized,
+    /// The panel is too cold or too hot to be refreshed
+    TemperatureOutOfRange,
+    /// Every remembered bond belongs to an admin, so no further central can bond
+    BondsFull,
+}
+
+impl Error {
+    /// Numeric code reported to the hub, stable across firmware versions.
+    pub fn code(&self) -> u8 {
+        match self {
+            Error::Spi(_) => 0x01,
+            Error::DisplayUnavailable => 0x02,
+            Error::CursorOutOfRange => 0x03,
+            Error::InvalidLength => 0x04,
+            Error::Gatt(_) => 0x05,
+            Error::Flash(_) => 0x06,
+            Error::IncompleteTransfer => 0x07,
+            Error::InvalidValue => 0x08,
+            Error::StorageUnavailable => 0x09,
+            Error::UploadBusy => 0x0A,
+            Error::Unauthenticated => 0x0B,
+            Error::Unauthorized => 0x0C,
+            Error::TemperatureOutOfRange => 0x0D,
+            Error::BondsFull => 0x0E,
+        }
+    }
+
+    /// ATT error code used to reject the GATT request that caused this error.
+    pub fn att_error(&self) -> AttErrorCode {
+        match self {
+            Error::CursorOutOfRange => AttErrorCode::INVALID_OFFSET,
+            Error::InvalidLength => AttErrorCode::INVALID_ATTRIBUTE_VALUE_LENGTH,
+            Error::DisplayUnavailable | Error::StorageUnavailable | Error::BondsFull => {
+                AttErrorCode::INSUFFICIENT_RESOURCES
+            }
+            Error::IncompleteTransfer | Error::InvalidValue => AttErrorCode::VALUE_NOT_ALLOWED,
+            Error::UploadBusy => AttErrorCode::WRITE_NOT_PERMITTED,
+            Error::Unauthenticated => AttErrorCode::INSUFFICIENT_AUTHENTICATION,
+            Error::Unauthorized => AttErrorCode::INSUFFICIENT_AUTHORISATION,
+            Error::Spi(_) | Error::Gatt(_) | Error::Flash(_) | Error::TemperatureOutOfRange => {
+                AttErrorCode::UNLIKELY_ERROR
+            }
+        }
+    }
+}
+
+impl From<DeviceError<spi::Error, Infallible>> for Error {
+    fn from(value: DeviceError<spi::Error, Infallible>) -> Self {
+        match value {
+            DeviceError::Spi(e) => Error::Spi(e),
+            DeviceError::Cs(e) => match e {},
+        }
+    }
+}
+
+impl From<periphery_protocol::Error> for Error {
+    fn from(value: periphery_protocol::Error) -> Self {
+        match value {
+            periphery_protocol::Error::CursorOutOfRange => Error::CursorOutOfRange,
+            periphery_protocol::Error::InvalidLength => Error::InvalidLength,
+            periphery_protocol::Error::InvalidValue => Error::InvalidValue,
+        }
+    }
+}
+
+impl From<&Error> for TransferStatus {
+    fn from(value: &Error) -> Self {
+        TransferStatus::Error { code: value.code() }
+    }
+}
+
+impl From<trouble_host::Error> for Error {
+    fn from(value: trouble_host::Error) -> Self {
+        Error::Gatt(value)
+    }
+}
+
+impl From<flash::Error> for Error {
+    fn from(value: flash::Error) -> Self {
+        Error::Flash(value)
+    }
+}
@@ -0,0 +1,118 @@
+use defmt::info;
+use embassy_rp::flash::ERASE_SIZE;
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::Mutex};
+use periphery_render::font::{HEADER_LEN, StoredFont};
+
+use crate::error::Error;
+use crate::storage::{self, FONT_DATA_OFFSET, STORAGE};
+
+/// Number of fonts that can be uploaded
+pub const FONT_COUNT: usize = 4;
+/// Space reserved for each font
+const FONT_MAX_LEN: usize = 0x1_0000;
+/// Font uploads are written in pieces prefixed with the font id and their offset (u32)
+const PIECE_HEADER_LEN: usize = 5;
+
+static UPLOAD: Mutex<CriticalSectionRawMutex, Upload> = Mutex::new(Upload {
+    id: 0,
+    len: 0,
+    total: 0,
+    first: [0xFF; ERASE_SIZE],
+    current: [0xFF; ERASE_SIZE],
+});
+
+/// Fonts are written to flash sector by sector. The first sector holding the header is
+/// written last, so a font only becomes visible once it is complete.
+struct Upload {
+    id: usize,
+    len: usize,
+    total: usize,
+    first: [u8; ERASE_SIZE],
+    current: [u8; ERASE_SIZE],
+}
+
+/// The uploaded font with `id`, if it is complete.
+pub fn get(id: usize) -> Option<StoredFont<'static>> {
+    if id >= FONT_COUNT {
+        return None;
+    }
+    StoredFont::parse(storage::mapped(font_offset(id), FONT_MAX_LEN)).ok()
+}
+
+/// Write a piece of a font upload to flash, returning true once the font is complete.
+///
+/// A piece at offset 0 starts a new upload and has to contain the font header.
+pub async fn receive(piece: &[u8]) -> Result<bool, Error> {
+    if piece.len() < PIECE_HEADER_LEN {
+        return Err(Error::InvalidLength);
+    }
+    let (header, data) = piece.split_at(PIECE_HEADER_LEN);
+    let id = header[0] as usize;
+    let offset = u32::from_le_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    if id >= FONT_COUNT {
+        return Err(Error::InvalidValue);
+    }
+
+    let mut upload = UPLOAD.lock().await;
+    let mut storage = STORAGE.lock().await;
+    let storage = storage.as_mut().ok_or(Error::StorageUnavailable)?;
+    if offset == 0 {
+        if data.len() < HEADER_LEN {
+            return Err(Error::InvalidLength);
+        }
+        let total = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        if !(HEADER_LEN..=FONT_MAX_LEN).contains(&total) {
+            return Err(Error::InvalidLength);
+        }
+        // hide the previous font while it is overwritten
+        storage.erase(font_offset(id), ERASE_SIZE)?;
+        *upload = Upload {
+            id,
+            len: 0,
+            total,
+            first: [0xFF; ERASE_SIZE],
+            current: [0xFF; ERASE_SIZE],
+        };
+        info!("[fonts] receiving font {} ({} bytes)", id, total);
+    }
+    if id != upload.id || offset != upload.len || offset + data.len() > upload.total {
+        return Err(Error::CursorOutOfRange);
+    }
+
+    let mut data = data;
+    while !data.is_empty() {
+        let sector = upload.len / ERASE_SIZE;
+        let start = upload.len % ERASE_SIZE;
+        let take = (ERASE_SIZE - start).min(data.len());
+        let buffer = if sector == 0 {
+            &mut upload.first
+        } else {
+            &mut upload.current
+        };
+        buffer[start..start + take].copy_from_slice(&data[..take]);
+        upload.len += take;
+        data = &data[take..];
+
+        let sector_full = upload.len % ERASE_SIZE == 0;
+        if sector != 0 && (sector_full || upload.len == upload.total) {
+            let offset = font_offset(id) + (sector * ERASE_SIZE) as u32;
+            storage.write(offset, &upload.current)?;
+            upload.current = [0xFF; ERASE_SIZE];
+        }
+    }
+    if upload.len < upload.total {
+        return Ok(false);
+    }
+
+    storage.write(font_offset(id), &upload.first)?;
+    if let Err(e) = StoredFont::parse(storage::mapped(font_offset(id), FONT_MAX_LEN)) {
+        storage.erase(font_offset(id), ERASE_SIZE)?;
+        return Err(e.into());
+    }
+    info!("[fonts] stored font {}", id);
+    Ok(true)
+}
+
+fn font_offset(id: usize) -> u32 {
+    FONT_DATA_OFFSET + (id * FONT_MAX_LEN) as u32
+}
@@ -0,0 +1,76 @@
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::Mutex};
+use embedded_graphics::prelude::*;
+use epd_waveshare::color::TriColor;
+use periphery_render::layout::render;
+
+use crate::error::Error;
+use crate::fonts;
+
+/// Largest layout accepted, text-heavy dashboards need a few hundred bytes
+pub const LAYOUT_MAX_LEN: usize = 2048;
+/// Layouts are written in pieces prefixed with their offset (u16) into the layout
+const PIECE_HEADER_LEN: usize = 2;
+
+static LAYOUT: Mutex<CriticalSectionRawMutex, Received> = Mutex::new(Received {
+    data: [0; LAYOUT_MAX_LEN],
+    len: 0,
+});
+
+struct Received {
+    data: [u8; LAYOUT_MAX_LEN],
+    len: usize,
+}
+
+/// Append a piece written to the layout characteristic, returning true once the
+/// whole layout has been received.
+///
+/// A layout starts with its total length (u16) followed by drawing commands, see [`render`].
+pub async fn receive(piece: &[u8]) -> Result<bool, Error> {
+    if piece.len() < PIECE_HEADER_LEN {
+        return Err(Error::InvalidLength);
+    }
+    let (header, data) = piece.split_at(PIECE_HEADER_LEN);
+    let offset = u16::from_le_bytes([header[0], header[1]]) as usize;
+
+    let mut layout = LAYOUT.lock().await;
+    // a piece at offset 0 starts a new layout
+    if offset == 0 {
+        layout.len = 0;
+    }
+    if offset != layout.len {
+        return Err(Error::CursorOutOfRange);
+    }
+    if offset + data.len() > LAYOUT_MAX_LEN {
+        layout.len = 0;
+        return Err(Error::InvalidLength);
+    }
+    layout.data[offset..offset + data.len()].copy_from_slice(data);
+    layout.len += data.len();
+
+    if layout.len < 2 {
+        return Ok(false);
+    }
+    let total = u16::from_le_bytes([layout.data[0], layout.data[1]]) as usize;
+    if total > LAYOUT_MAX_LEN || layout.len > total {
+        layout.len = 0;
+        return Err(Error::InvalidLength);
+    }
+    Ok(layout.len == total)
+}
+
+/// Render the received layout onto a white frame.
+pub async fn render_received<D>(target: &mut D) -> Result<(), Error>
+where
+    D: DrawTarget<Color = TriColor>,
+{
+    let mut layout = LAYOUT.lock().await;
+    let len = core::mem::take(&mut layout.len);
+    if len < 2 {
+        return Err(Error::InvalidLength);
+    }
+    let Ok(()) = target.clear(TriColor::White) else {
+        return Err(Error::InvalidValue);
+    };
+    render(target, &layout.data[2..len], fonts::get)?;
+    Ok(())
+}
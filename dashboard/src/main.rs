@@ -0,0 +1,135 @@
+#![no_std]
+#![no_main]
+
+mod bluetooth;
+mod clock;
+mod display;
+mod error;
+mod fonts;
+mod layout;
+mod refresh;
+mod rtc;
+mod schedule;
+mod settings;
+mod slots;
+mod state;
+mod storage;
+mod temperature;
+mod widget;
+
+use embassy_executor::Spawner;
+use embassy_rp::adc::{self, Adc};
+use embassy_rp::block::ImageDef;
+use embassy_rp::gpio::{Input, Level, Output, Pull};
+use embassy_rp::peripherals::TRNG;
+use embassy_rp::spi::Spi;
+use embassy_rp::trng::{self, Trng};
+use embassy_rp::{self as hal, bind_interrupts, spi};
+use embassy_time::{Instant, Timer};
+
+use embedded_hal_bus::spi::ExclusiveDevice;
+
+//Panic Handler
+use panic_probe as _;
+// Defmt Logging
+use defmt::{error, info};
+use defmt_rtt as _;
+
+/// Tell the Boot ROM about our application
+#[unsafe(link_section = ".start_block")]
+#[used]
+pub static IMAGE_DEF: ImageDef = hal::block::ImageDef::secure_exe();
+
+// Log with the wall-clock time once it is known, with the uptime before
+defmt::timestamp!(
+    "{=u64:tms}",
+    clock::now_millis().unwrap_or_else(|| Instant::now().as_millis())
+);
+
+bind_interrupts!(struct Irqs {
+    TRNG_IRQ => trng::InterruptHandler<TRNG>;
+});
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    let p = embassy_rp::init(Default::default());
+
+    info!("Starting periphery_dashboard");
+
+    let spi = Spi::new_blocking_txonly(p.SPI1, p.PIN_10, p.PIN_11, spi::Config::default());
+    let cs_pin = Output::new(p.PIN_9, Level::High);
+    let spi_dev =
+        ExclusiveDevice::new_no_delay(spi, cs_pin).expect("display count 1 should not panic");
+
+    let busy_pin = Input::new(p.PIN_13, Pull::Up);
+    let dc_pin = Output::new(p.PIN_8, Level::Low);
+    let rst_pin = Output::new(p.PIN_12, Level::Low);
+
+    // Keep running without a display, so the hub can still connect and read the error
+    match display::init(spi_dev, busy_pin, dc_pin, rst_pin) {
+        Ok(mut d) => {
+            info!("initialized Display");
+            if let Err(e) = d.clear() {
+                error!("failed to clear Display: {:?}", e);
+            }
+            *display::DISPLAY.lock().await = Some(d);
+        }
+        Err(e) => {
+            error!("failed to initialize Display: {:?}", e);
+            state::update_device_state(|state| {
+                state.error_flags |= state::ERROR_DISPLAY_UNAVAILABLE
+            });
+        }
+    }
+
+    *storage::STORAGE.lock().await = Some(storage::Storage::new(p.FLASH));
+
+    let (bt_controller, mac_addr) = bluetooth::controller::init(
+        p.PIN_23, p.PIN_25, p.PIO0, p.PIN_24, p.PIN_29, p.DMA_CH0, &spawner,
+    )
+    .await;
+    info!("initialized Bluetooth Controller");
+    settings::load(&mac_addr).await;
+    clock::init(settings::get().await.clock);
+    if let Some(display) = display::DISPLAY.lock().await.as_mut() {
+        display
+            .frame_mut()
+            .set_orientation(settings::get().await.orientation);
+    }
+    slots::load().await;
+    schedule::load().await;
+    spawner.spawn(slots::slideshow_task().unwrap());
+    spawner.spawn(schedule::scheduler_task().unwrap());
+    spawner.spawn(widget::widget_task().unwrap());
+    spawner.spawn(refresh::refresh_task().unwrap());
+    let adc = Adc::new_blocking(p.ADC, adc::Config::default());
+    let sensor = adc::Channel::new_temp_sensor(p.ADC_TEMP_SENSOR);
+    spawner.spawn(temperature::temperature_task(adc, sensor).unwrap());
+    // seeds the key generation used when pairing
+    let rng = Trng::new(p.TRNG, Irqs, trng::Config::default());
+    bluetooth::peripheral::run(bt_controller, spawner, mac_addr, rng).await;
+
+    // let mut led = Output::new(p.PIN_15, Level::Low);
+    // loop {
+    //     info!("Turning on LED");
+    //     led.set_high(); // Turn on the LED
+    //     Timer::after_millis(200).await;
+    //     led.set_low(); // Turn off the LED
+    //     Timer::after_millis(600).await;
+    // }
+}
+
+// Program metadata for `picotool info`.
+// This isn't needed, but it's recommended to have these minimal entries.
+#[unsafe(link_section = ".bi_entries")]
+#[used]
+pub static PICOTOOL_ENTRIES: [embassy_rp::binary_info::EntryAddr; 4] = [
+    embassy_rp::binary_info::rp_program_name!(c"periphery_dashboard"),
+    embassy_rp::binary_info::rp_program_description!(
+        c"A dashboard displaying images on a e-ink display, provided by a hub over Bluetooth LE"
+    ),
+    embassy_rp::binary_info::rp_cargo_version!(),
+    embassy_rp::binary_info::rp_program_build_attribute!(),
+];
+
+// End of file
@@ -0,0 +1,193 @@
+use core::cell::Cell;
+
+use defmt::{info, warn};
+use embassy_futures::select::select;
+use embassy_sync::{
+    blocking_mutex::{Mutex, raw::CriticalSectionRawMutex},
+    signal::Signal,
+};
+use embassy_time::{Duration, Instant, Timer};
+use epd_waveshare::color::TriColor;
+
+use crate::bluetooth::transfer::upload_active;
+use crate::clock;
+use crate::display::{DISPLAY, FRAME_LEN};
+use crate::error::Error;
+use crate::settings;
+use crate::storage::{CLEAN_SCRATCH_OFFSET, STORAGE, mapped};
+
+/// Encoded as `[refreshes (u32), refreshes since the last clean (u32), seconds since the
+/// last clean (u32, 0xFFFFFFFF if there was none since the start)]`
+pub const REFRESH_STATS_LEN: usize = 12;
+/// Colors the panel is driven to during a clean cycle, before the frame is shown again
+const CLEAN_CYCLE: [TriColor; 3] = [TriColor::White, TriColor::Black, TriColor::White];
+/// A night clean only happens once per night, even if the display refreshes in between
+const NIGHT_CLEAN_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// Signaled after every refresh, so the policy is checked right away
+static REFRESHED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+static STATS: Mutex<CriticalSectionRawMutex, Cell<RefreshStats>> =
+    Mutex::new(Cell::new(RefreshStats {
+        refreshes: 0,
+        since_clean: 0,
+        last_clean: None,
+    }));
+
+#[derive(Clone, Copy)]
+struct RefreshStats {
+    refreshes: u32,
+    since_clean: u32,
+    last_clean: Option<Instant>,
+}
+
+/// When to run a clean cycle, driving the whole panel white, black and white again to
+/// remove ghosting and chromatic bleed accumulated by the refreshes in between.
+#[derive(Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub struct RefreshPolicy {
+    /// Clean after this many refreshes
+    pub max_refreshes: Option<u16>,
+    /// Clean once this long has passed since the last clean and the display refreshed since
+    pub max_age: Option<Duration>,
+    /// Clean during this local hour if the display refreshed since the last clean, so the
+    /// flashing cycle doesn't happen while someone looks at the dashboard
+    pub night_hour: Option<u8>,
+}
+
+impl Default for RefreshPolicy {
+    fn default() -> Self {
+        RefreshPolicy {
+            max_refreshes: Some(50),
+            max_age: None,
+            night_hour: Some(3),
+        }
+    }
+}
+
+impl RefreshPolicy {
+    /// Encoded as `[max refreshes (u16, 0 disables), max age (u16, h, 0 disables),
+    /// night hour (0xFF disables)]`, little endian.
+    pub const ENCODED_LEN: usize = 5;
+
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut encoded = [0u8; Self::ENCODED_LEN];
+        let max_age = self.max_age.map_or(0, |age| age.as_secs() / 3600);
+        encoded[0..2].copy_from_slice(&self.max_refreshes.unwrap_or(0).to_le_bytes());
+        encoded[2..4].copy_from_slice(&(max_age as u16).to_le_bytes());
+        encoded[4] = self.night_hour.unwrap_or(0xFF);
+        encoded
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let data: &[u8; Self::ENCODED_LEN] = data.try_into().map_err(|_| Error::InvalidLength)?;
+        let max_refreshes = u16::from_le_bytes([data[0], data[1]]);
+        let max_age = u16::from_le_bytes([data[2], data[3]]);
+        let night_hour = match data[4] {
+            0xFF => None,
+            hour @ 0..24 => Some(hour),
+            _ => return Err(Error::InvalidValue),
+        };
+        Ok(RefreshPolicy {
+            max_refreshes: (max_refreshes != 0).then_some(max_refreshes),
+            max_age: (max_age != 0).then(|| Duration::from_secs(u64::from(max_age) * 3600)),
+            night_hour,
+        })
+    }
+
+    fn clean_due(&self, stats: &RefreshStats) -> bool {
+        if stats.since_clean == 0 {
+            return false;
+        }
+        let since_clean = stats.last_clean.map(|last_clean| last_clean.elapsed());
+        let too_many = self
+            .max_refreshes
+            .is_some_and(|max| stats.since_clean >= u32::from(max));
+        let too_old = self
+            .max_age
+            .is_some_and(|max| since_clean.is_none_or(|age| age >= max));
+        let night = self.night_hour.is_some_and(|hour| {
+            let now = clock::local_time().map(|time| time.minutes / 60);
+            now == Some(hour.into()) && since_clean.is_none_or(|age| age >= NIGHT_CLEAN_INTERVAL)
+        });
+        too_many || too_old || night
+    }
+}
+
+/// Count a refresh of the panel.
+pub fn count() {
+    STATS.lock(|stats| {
+        let mut value = stats.get();
+        value.refreshes = value.refreshes.wrapping_add(1);
+        value.since_clean = value.since_clean.saturating_add(1);
+        stats.set(value);
+    });
+    REFRESHED.signal(());
+}
+
+/// Refresh counters exposed over GATT, see [`REFRESH_STATS_LEN`].
+pub fn stats() -> [u8; REFRESH_STATS_LEN] {
+    let stats = STATS.lock(|stats| stats.get());
+    let since_clean = stats
+        .last_clean
+        .map_or(u32::MAX, |last_clean| last_clean.elapsed().as_secs() as u32);
+    let mut encoded = [0u8; REFRESH_STATS_LEN];
+    encoded[0..4].copy_from_slice(&stats.refreshes.to_le_bytes());
+    encoded[4..8].copy_from_slice(&stats.since_clean.to_le_bytes());
+    encoded[8..12].copy_from_slice(&since_clean.to_le_bytes());
+    encoded
+}
+
+/// Run a clean cycle and show the current frame again.
+///
+/// The frame buffer is needed to drive the panel, so the frame is parked in flash meanwhile.
+pub async fn clean() -> Result<(), Error> {
+    let mut guard = DISPLAY.lock().await;
+    let display = guard.as_mut().ok_or(Error::DisplayUnavailable)?;
+    // the display buffer holds the frame being uploaded
+    if upload_active() {
+        return Err(Error::UploadBusy);
+    }
+    let mut storage = STORAGE.lock().await;
+    let storage = storage.as_mut().ok_or(Error::StorageUnavailable)?;
+    storage.write(CLEAN_SCRATCH_OFFSET, display.frame().buffer())?;
+
+    info!("[refresh] cleaning the display");
+    let mut result = Ok(());
+    for color in CLEAN_CYCLE {
+        if let Err(e) = display.fill_panel(color) {
+            result = Err(e);
+            break;
+        }
+    }
+    // show the frame again even if the cycle failed half way
+    display
+        .frame_mut()
+        .load_buffer(mapped(CLEAN_SCRATCH_OFFSET, FRAME_LEN))?;
+    display.display_buffer()?;
+    result?;
+
+    STATS.lock(|stats| {
+        let mut value = stats.get();
+        value.since_clean = 0;
+        value.last_clean = Some(Instant::now());
+        stats.set(value);
+    });
+    Ok(())
+}
+
+/// Run clean cycles according to the [`RefreshPolicy`].
+#[embassy_executor::task]
+pub async fn refresh_task() {
+    loop {
+        // checked every minute as well, to catch the night hour and policy changes
+        select(REFRESHED.wait(), Timer::after(Duration::from_secs(60))).await;
+
+        let policy = settings::get().await.refresh;
+        if !policy.clean_due(&STATS.lock(|stats| stats.get())) {
+            continue;
+        }
+        if let Err(e) = clean().await {
+            warn!("[refresh] error cleaning the display: {:?}", e);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use embassy_rp::pac;
+
+/// Writes to the power manager are ignored unless the upper half carries the password
+const PASSWORD: u32 = 0x5AFE << 16;
+
+/// Frequency of the crystal on the Pico 2 W
+const XOSC_KHZ: u16 = 12_000;
+
+/// Start the always-on timer of the power manager, unless it kept running since before
+/// the last reset.
+///
+/// The timer keeps counting milliseconds while the cores sleep and across resets that
+/// don't cut the power, so it holds the wall-clock time instead of the uptime.
+///
+/// Ticks are derived from the crystal, which is far more accurate than the low power
+/// oscillator. The remaining drift is corrected by the clock on every sync.
+pub fn start() {
+    if is_running() {
+        return;
+    }
+    let powman = pac::POWMAN;
+    powman.xosc_freq_khz_int().write(|w| {
+        w.set_xosc_freq_khz_int(XOSC_KHZ);
+        w.0 |= PASSWORD;
+    });
+    powman.xosc_freq_khz_frac().write(|w| w.0 = PASSWORD);
+    powman.timer().modify(|w| {
+        w.0 = (w.0 & 0xFFFF) | PASSWORD;
+        w.set_use_xosc(true);
+    });
+    set_running(true);
+}
+
+pub fn is_running() -> bool {
+    pac::POWMAN.timer().read().run()
+}
+
+/// Milliseconds counted by the timer.
+pub fn millis() -> u64 {
+    let powman = pac::POWMAN;
+    // the upper half may change between the two reads
+    loop {
+        let upper = powman.read_time_upper().read();
+        let lower = powman.read_time_lower().read();
+        if powman.read_time_upper().read() == upper {
+            return (u64::from(upper) << 32) | u64::from(lower);
+        }
+    }
+}
+
+/// Set the milliseconds counted by the timer, which has to be stopped meanwhile.
+pub fn set_millis(millis: u64) {
+    let powman = pac::POWMAN;
+    set_running(false);
+    powman.set_time_63to48().write(|w| {
+        w.set_set_time_63to48((millis >> 48) as u16);
+        w.0 |= PASSWORD;
+    });
+    powman.set_time_47to32().write(|w| {
+        w.set_set_time_47to32((millis >> 32) as u16);
+        w.0 |= PASSWORD;
+    });
+    powman.set_time_31to16().write(|w| {
+        w.set_set_time_31to16((millis >> 16) as u16);
+        w.0 |= PASSWORD;
+    });
+    powman.set_time_15to0().write(|w| {
+        w.set_set_time_15to0(millis as u16);
+        w.0 |= PASSWORD;
+    });
+    set_running(true);
+}
+
+fn set_running(run: bool) {
+    pac::POWMAN.timer().modify(|w| {
+        w.0 = (w.0 & 0xFFFF) | PASSWORD;
+        w.set_run(run);
+    });
+}
@@ -0,0 +1,121 @@
+use defmt::{info, warn};
+use embassy_futures::select::select;
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::Mutex, signal::Signal};
+use embassy_time::{Duration, Timer};
+use periphery_protocol::hash::fnv1a;
+use periphery_protocol::schedule::Schedule;
+
+use crate::clock;
+use crate::error::Error;
+use crate::slots::{self, SLOT_COUNT};
+use crate::storage::{SCHEDULE_OFFSET, STORAGE};
+
+pub use periphery_protocol::schedule::SCHEDULE_LEN;
+
+/// Marks a valid schedule in flash
+const MAGIC: u32 = 0x5343_4844;
+/// Magic (u32) in front of the schedule, checksum (u32) following it
+const RECORD_LEN: usize = 4 + SCHEDULE_LEN + 4;
+
+static SCHEDULE: Mutex<CriticalSectionRawMutex, Schedule> = Mutex::new(Schedule::new());
+/// Signaled when the schedule or the clock changed, so the schedule is evaluated again
+pub static SCHEDULE_CHANGED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Load the schedule from flash.
+pub async fn load() {
+    let mut record = [0u8; RECORD_LEN];
+    let result = {
+        let mut storage = STORAGE.lock().await;
+        match storage.as_mut() {
+            Some(storage) => storage.read(SCHEDULE_OFFSET, &mut record),
+            None => Err(Error::StorageUnavailable),
+        }
+    };
+    if let Err(e) = result {
+        warn!("[schedule] error reading schedule: {:?}", e);
+        return;
+    }
+
+    let magic = u32::from_le_bytes([record[0], record[1], record[2], record[3]]);
+    let end = RECORD_LEN - 4;
+    let checksum = u32::from_le_bytes([
+        record[end],
+        record[end + 1],
+        record[end + 2],
+        record[end + 3],
+    ]);
+    if magic != MAGIC || checksum != fnv1a(&record[..end]) {
+        info!("[schedule] no stored schedule");
+        return;
+    }
+    match Schedule::decode(&record[4..end], SLOT_COUNT) {
+        Ok(schedule) => {
+            info!("[schedule] loaded {} entries", schedule.entries().len());
+            *SCHEDULE.lock().await = schedule;
+        }
+        Err(e) => warn!("[schedule] stored schedule is invalid: {:?}", e),
+    }
+}
+
+/// Replace the schedule with the one written to the schedule characteristic and persist it.
+pub async fn set(data: &[u8]) -> Result<(), Error> {
+    let schedule = Schedule::decode(data, SLOT_COUNT)?;
+
+    let mut record = [0xFFu8; RECORD_LEN];
+    record[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+    record[4..4 + SCHEDULE_LEN].copy_from_slice(data);
+    let end = RECORD_LEN - 4;
+    let checksum = fnv1a(&record[..end]);
+    record[end..].copy_from_slice(&checksum.to_le_bytes());
+    {
+        let mut storage = STORAGE.lock().await;
+        let storage = storage.as_mut().ok_or(Error::StorageUnavailable)?;
+        storage.write(SCHEDULE_OFFSET, &record)?;
+    }
+
+    info!("[schedule] stored {} entries", schedule.entries().len());
+    *SCHEDULE.lock().await = schedule;
+    SCHEDULE_CHANGED.signal(());
+    Ok(())
+}
+
+/// The schedule as exposed over GATT.
+pub async fn encoded() -> [u8; SCHEDULE_LEN] {
+    SCHEDULE.lock().await.encode()
+}
+
+/// Slot to be shown according to the schedule, the first matching entry wins.
+pub async fn active_slot() -> Option<usize> {
+    let time = clock::local_time()?;
+    let slot = SCHEDULE
+        .lock()
+        .await
+        .active_slot(time.weekday, time.minutes)?;
+    Some(slot as usize)
+}
+
+/// Show the scheduled images, evaluating the schedule every minute.
+///
+/// The scheduled image is only shown when the active entry changes, so images pushed by
+/// the hub in between stay on the display until the next change.
+#[embassy_executor::task]
+pub async fn scheduler_task() {
+    let mut shown = None;
+    loop {
+        let slot = active_slot().await;
+        if slot != shown {
+            match slot {
+                Some(index) => match slots::show(index).await {
+                    Ok(()) => shown = slot,
+                    Err(e) => warn!("[schedule] error showing slot {}: {:?}", index, e),
+                },
+                None => shown = None,
+            }
+        }
+
+        // wake up at the start of the next minute
+        let seconds = clock::local_time().map_or(0, |time| time.seconds);
+        let delay = Duration::from_secs(60 - u64::from(seconds));
+        select(SCHEDULE_CHANGED.wait(), Timer::after(delay)).await;
+    }
+}
@@ -0,0 +1,266 @@
+use core::fmt::Write;
+
+use defmt::{info, warn};
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::Mutex};
+use heapless::String;
+use periphery_protocol::hash::fnv1a;
+
+use crate::bluetooth::advertising::AdvertisingConfig;
+use crate::clock::ClockConfig;
+use crate::display::OrientationConfig;
+use crate::error::Error;
+use crate::refresh::RefreshPolicy;
+use crate::slots::SlideshowConfig;
+use crate::storage::{SETTINGS_OFFSET, STORAGE};
+use crate::widget::WidgetConfig;
+
+pub const NAME_MAX_LEN: usize = 20;
+pub const LABEL_MAX_LEN: usize = 32;
+
+/// Marks a valid settings record in flash
+const MAGIC: u32 = 0x5345_5454;
+/// Size of the persisted settings record
+const RECORD_LEN: usize = 256;
+/// Magic (u32) and payload length (u16) in front of the payload
+const HEADER_LEN: usize = 6;
+/// Checksum (u32) following the payload
+const CHECKSUM_LEN: usize = 4;
+
+static SETTINGS: Mutex<CriticalSectionRawMutex, Option<Settings>> = Mutex::new(None);
+
+/// Settings persisted in flash and changeable over the `SettingsService`.
+#[derive(Clone, defmt::Format)]
+pub struct Settings {
+    /// Name used for advertising and as GAP device name
+    pub name: String<NAME_MAX_LEN>,
+    /// Room or location the device is mounted in
+    pub label: String<LABEL_MAX_LEN>,
+    pub advertising: AdvertisingConfig,
+    pub slideshow: SlideshowConfig,
+    pub clock: ClockConfig,
+    pub widget: WidgetConfig,
+    pub orientation: OrientationConfig,
+    pub refresh: RefreshPolicy,
+}
+
+impl Settings {
+    /// Default settings, with a name derived from the MAC address so units are distinguishable.
+    fn new(mac_addr: &[u8; 6]) -> Self {
+        let mut name = String::new();
+        // "Dash-XXXX" fits into the scan response next to the service UUID
+        let _ = write!(name, "Dash-{:02X}{:02X}", mac_addr[4], mac_addr[5]);
+        Settings {
+            name,
+            label: String::new(),
+            advertising: AdvertisingConfig::default(),
+            slideshow: SlideshowConfig::default(),
+            clock: ClockConfig::default(),
+            widget: WidgetConfig::default(),
+            orientation: OrientationConfig::default(),
+            refresh: RefreshPolicy::default(),
+        }
+    }
+
+    /// Encoded as `[name length, name, label length, label, advertising config,
+    /// slideshow config, clock config, widget config, orientation config, refresh policy]`.
+    ///
+    /// Fields are only ever appended, so records written by older firmware stay readable.
+    fn encode(&self, buf: &mut [u8]) -> usize {
+        let mut writer = Writer { buf, len: 0 };
+        writer.text(&self.name);
+        writer.text(&self.label);
+        writer.bytes(&self.advertising.encode());
+        writer.bytes(&self.slideshow.encode());
+        writer.bytes(&self.clock.encode());
+        writer.bytes(&self.widget.encode());
+        writer.bytes(&self.orientation.encode());
+        writer.bytes(&self.refresh.encode());
+        writer.len
+    }
+
+    /// Decode a payload, keeping the values of `self` for fields missing from older records.
+    fn decode(mut self, payload: &[u8]) -> Self {
+        let mut reader = Reader { data: payload };
+        if let Some(name) = reader.text() {
+            self.name = name;
+        }
+        if let Some(label) = reader.text() {
+            self.label = label;
+        }
+        if let Some(config) = reader
+            .bytes(AdvertisingConfig::ENCODED_LEN)
+            .and_then(|data| AdvertisingConfig::decode(data).ok())
+        {
+            self.advertising = config;
+        }
+        if let Some(config) = reader
+            .bytes(SlideshowConfig::ENCODED_LEN)
+            .and_then(|data| SlideshowConfig::decode(data).ok())
+        {
+            self.slideshow = config;
+        }
+        if let Some(config) = reader
+            .bytes(ClockConfig::ENCODED_LEN)
+            .and_then(|data| ClockConfig::decode(data).ok())
+        {
+            self.clock = config;
+        }
+        if let Some(config) = reader
+            .bytes(WidgetConfig::ENCODED_LEN)
+            .and_then(|data| WidgetConfig::decode(data).ok())
+        {
+            self.widget = config;
+        }
+        if let Some(config) = reader
+            .bytes(OrientationConfig::ENCODED_LEN)
+            .and_then(|data| OrientationConfig::decode(data).ok())
+        {
+            self.orientation = config;
+        }
+        if let Some(policy) = reader
+            .bytes(RefreshPolicy::ENCODED_LEN)
+            .and_then(|data| RefreshPolicy::decode(data).ok())
+        {
+            self.refresh = policy;
+        }
+        self
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            name: String::try_from("Dashboard").unwrap_or_default(),
+            label: String::new(),
+            advertising: AdvertisingConfig::default(),
+            slideshow: SlideshowConfig::default(),
+            clock: ClockConfig::default(),
+            widget: WidgetConfig::default(),
+            orientation: OrientationConfig::default(),
+            refresh: RefreshPolicy::default(),
+        }
+    }
+}
+
+/// Load the settings from flash, falling back to defaults if there are none yet.
+pub async fn load(mac_addr: &[u8; 6]) {
+    let defaults = Settings::new(mac_addr);
+    let mut record = [0u8; RECORD_LEN];
+    let settings = match read_record(&mut record).await {
+        Ok(Some(payload)) => defaults.decode(payload),
+        Ok(None) => {
+            info!("[settings] no stored settings, using defaults");
+            defaults
+        }
+        Err(e) => {
+            warn!("[settings] error reading settings: {:?}", e);
+            defaults
+        }
+    };
+    info!("[settings] loaded: {:?}", settings);
+    *SETTINGS.lock().await = Some(settings);
+}
+
+pub async fn get() -> Settings {
+    SETTINGS.lock().await.clone().unwrap_or_default()
+}
+
+/// Change the settings and persist them to flash.
+pub async fn update(f: impl FnOnce(&mut Settings)) -> Result<(), Error> {
+    let mut guard = SETTINGS.lock().await;
+    let mut settings = guard.clone().unwrap_or_default();
+    f(&mut settings);
+
+    let mut record = [0xFFu8; RECORD_LEN];
+    let len = settings.encode(&mut record[HEADER_LEN..RECORD_LEN - CHECKSUM_LEN]);
+    record[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+    record[4..6].copy_from_slice(&(len as u16).to_le_bytes());
+    let checksum = fnv1a(&record[..HEADER_LEN + len]);
+    record[HEADER_LEN + len..HEADER_LEN + len + CHECKSUM_LEN]
+        .copy_from_slice(&checksum.to_le_bytes());
+
+    let mut storage = STORAGE.lock().await;
+    let storage = storage.as_mut().ok_or(Error::StorageUnavailable)?;
+    storage.write(SETTINGS_OFFSET, &record)?;
+
+    info!("[settings] stored: {:?}", settings);
+    *guard = Some(settings);
+    Ok(())
+}
+
+/// Read the settings record, returning its payload if the record is valid.
+async fn read_record(record: &mut [u8; RECORD_LEN]) -> Result<Option<&[u8]>, Error> {
+    let mut storage = STORAGE.lock().await;
+    let storage = storage.as_mut().ok_or(Error::StorageUnavailable)?;
+    storage.read(SETTINGS_OFFSET, record)?;
+
+    let magic = u32::from_le_bytes([record[0], record[1], record[2], record[3]]);
+    let len = u16::from_le_bytes([record[4], record[5]]) as usize;
+    if magic != MAGIC || HEADER_LEN + len + CHECKSUM_LEN > RECORD_LEN {
+        return Ok(None);
+    }
+    let checksum_at = HEADER_LEN + len;
+    let checksum = u32::from_le_bytes([
+        record[checksum_at],
+        record[checksum_at + 1],
+        record[checksum_at + 2],
+        record[checksum_at + 3],
+    ]);
+    if checksum != fnv1a(&record[..checksum_at]) {
+        warn!("[settings] stored settings are corrupted");
+        return Ok(None);
+    }
+    Ok(Some(&record[HEADER_LEN..checksum_at]))
+}
+
+/// Parse a text written over GATT, ignoring trailing zero padding.
+pub fn parse_text<const N: usize>(data: &[u8]) -> Result<String<N>, Error> {
+    let end = data.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    let text = core::str::from_utf8(&data[..end]).map_err(|_| Error::InvalidValue)?;
+    String::try_from(text).map_err(|_| Error::InvalidLength)
+}
+
+/// Pad a text with zeros to the size of its characteristic.
+pub fn pad_text<const N: usize>(text: &String<N>) -> [u8; N] {
+    let mut padded = [0u8; N];
+    padded[..text.len()].copy_from_slice(text.as_bytes());
+    padded
+}
+
+struct Writer<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl Writer<'_> {
+    fn bytes(&mut self, data: &[u8]) {
+        self.buf[self.len..self.len + data.len()].copy_from_slice(data);
+        self.len += data.len();
+    }
+
+    fn text(&mut self, text: &str) {
+        self.bytes(&[text.len() as u8]);
+        self.bytes(text.as_bytes());
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (value, rest) = self.data.split_at(len);
+        self.data = rest;
+        Some(value)
+    }
+
+    fn text<const N: usize>(&mut self) -> Option<String<N>> {
+        let len = *self.bytes(1)?.first()? as usize;
+        let text = core::str::from_utf8(self.bytes(len)?).ok()?;
+        String::try_from(text).ok()
+    }
+}
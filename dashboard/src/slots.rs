@@ -0,0 +1,352 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use bt_hci::param::ConnHandle;
+use defmt::{info, warn};
+use embassy_futures::select::{Either, select};
+use embassy_rp::flash::ERASE_SIZE;
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::Mutex, signal::Signal};
+use embassy_time::{Duration, Timer};
+use heapless::String;
+use periphery_protocol::hash::fnv1a;
+
+use crate::bluetooth::transfer::{release_upload, upload_active};
+use crate::display::{DISPLAY, Display, FRAME_LEN};
+use crate::error::Error;
+use crate::schedule;
+use crate::settings::{self, parse_text};
+use crate::state::record_refresh;
+use crate::storage::{SCRATCH_OFFSET, SLOT_DATA_OFFSET, SLOT_TABLE_OFFSET, STORAGE, mapped};
+use crate::widget;
+
+/// Number of images that can be stored on the device
+pub const SLOT_COUNT: usize = 8;
+pub const SLOT_NAME_MAX_LEN: usize = 16;
+/// Each slot occupies whole sectors, so it can be erased without touching its neighbours
+const SLOT_SIZE: u32 = FRAME_LEN.next_multiple_of(ERASE_SIZE) as u32;
+/// Marks a valid slot table in flash
+const MAGIC: u32 = 0x534c_4f54;
+/// Encoded as `[used, name length, name, frame hash (u32)]`
+const ENTRY_LEN: usize = 2 + SLOT_NAME_MAX_LEN + 4;
+/// Magic (u32) in front of the entries, checksum (u32) following them
+const TABLE_LEN: usize = 4 + SLOT_COUNT * ENTRY_LEN + 4;
+/// Encoded as `[used, name (zero padded), frame hash (u32)]` per slot, little endian
+pub const SLOT_TABLE_LEN: usize = SLOT_COUNT * (1 + SLOT_NAME_MAX_LEN + 4);
+/// Written to the slot control characteristic as `[command, slot, name (zero padded)]`
+pub const SLOT_CONTROL_LEN: usize = 2 + SLOT_NAME_MAX_LEN;
+
+const COMMAND_SELECT: u8 = 0x01;
+const COMMAND_SHOW: u8 = 0x02;
+const COMMAND_DELETE: u8 = 0x03;
+
+static SLOTS: Mutex<CriticalSectionRawMutex, [Option<Slot>; SLOT_COUNT]> =
+    Mutex::new([const { None }; SLOT_COUNT]);
+/// Slot the next committed frame is stored into instead of being displayed
+static TARGET: Mutex<CriticalSectionRawMutex, Option<(usize, String<SLOT_NAME_MAX_LEN>)>> =
+    Mutex::new(None);
+/// Set while the shown frame is parked in flash, because a frame for a slot is decoded
+/// into the display buffer
+static PARKED: AtomicBool = AtomicBool::new(false);
+/// Signaled when the slideshow settings changed, so the interval restarts
+pub static SLIDESHOW_CHANGED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// An image stored in flash.
+#[derive(Clone)]
+struct Slot {
+    name: String<SLOT_NAME_MAX_LEN>,
+    hash: u32,
+}
+
+/// Cycle through the stored images without the hub being connected.
+#[derive(Clone, Copy, defmt::Format)]
+pub struct SlideshowConfig {
+    /// Time each image is shown, `None` disables the slideshow
+    pub interval: Option<Duration>,
+    /// Slots taking part in the slideshow, bit `n` selects slot `n`
+    pub slots: u8,
+}
+
+impl Default for SlideshowConfig {
+    fn default() -> Self {
+        SlideshowConfig {
+            interval: None,
+            slots: 0xFF,
+        }
+    }
+}
+
+impl SlideshowConfig {
+    /// Encoded as `[interval (u16, s, 0 disables the slideshow), slot mask]`, little endian.
+    pub const ENCODED_LEN: usize = 3;
+
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let interval = self
+            .interval
+            .map_or(0, |interval| interval.as_secs() as u16);
+        let mut encoded = [0u8; Self::ENCODED_LEN];
+        encoded[0..2].copy_from_slice(&interval.to_le_bytes());
+        encoded[2] = self.slots;
+        encoded
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let data: &[u8; Self::ENCODED_LEN] = data.try_into().map_err(|_| Error::InvalidLength)?;
+        let interval = u16::from_le_bytes([data[0], data[1]]);
+        Ok(SlideshowConfig {
+            interval: (interval != 0).then(|| Duration::from_secs(interval.into())),
+            slots: data[2],
+        })
+    }
+}
+
+/// Load the slot table from flash.
+pub async fn load() {
+    let mut table = [0u8; TABLE_LEN];
+    let result = {
+        let mut storage = STORAGE.lock().await;
+        match storage.as_mut() {
+            Some(storage) => storage.read(SLOT_TABLE_OFFSET, &mut table),
+            None => Err(Error::StorageUnavailable),
+        }
+    };
+    if let Err(e) = result {
+        warn!("[slots] error reading slot table: {:?}", e);
+        return;
+    }
+
+    let magic = u32::from_le_bytes([table[0], table[1], table[2], table[3]]);
+    let end = TABLE_LEN - 4;
+    let checksum = u32::from_le_bytes([table[end], table[end + 1], table[end + 2], table[end + 3]]);
+    if magic != MAGIC || checksum != fnv1a(&table[..end]) {
+        info!("[slots] no stored images");
+        return;
+    }
+
+    let mut slots = SLOTS.lock().await;
+    for (slot, entry) in slots.iter_mut().zip(table[4..end].chunks_exact(ENTRY_LEN)) {
+        if entry[0] == 0 {
+            continue;
+        }
+        let len = (entry[1] as usize).min(SLOT_NAME_MAX_LEN);
+        let name = core::str::from_utf8(&entry[2..2 + len]).unwrap_or_default();
+        *slot = Some(Slot {
+            name: String::try_from(name).unwrap_or_default(),
+            hash: u32::from_le_bytes([entry[18], entry[19], entry[20], entry[21]]),
+        });
+    }
+    info!(
+        "[slots] {} stored images",
+        slots.iter().filter(|slot| slot.is_some()).count()
+    );
+}
+
+/// The slot table as exposed over GATT.
+pub async fn table() -> [u8; SLOT_TABLE_LEN] {
+    let mut encoded = [0u8; SLOT_TABLE_LEN];
+    let slots = SLOTS.lock().await;
+    for (slot, entry) in slots
+        .iter()
+        .zip(encoded.chunks_exact_mut(1 + SLOT_NAME_MAX_LEN + 4))
+    {
+        if let Some(slot) = slot {
+            entry[0] = 1;
+            entry[1..1 + slot.name.len()].copy_from_slice(slot.name.as_bytes());
+            entry[1 + SLOT_NAME_MAX_LEN..].copy_from_slice(&slot.hash.to_le_bytes());
+        }
+    }
+    encoded
+}
+
+/// Execute a command written to the slot control characteristic:
+///
+/// - select: store the next committed frame into the slot under the given name
+/// - show: display the stored image right away
+/// - delete: forget the stored image
+pub async fn control(data: &[u8]) -> Result<(), Error> {
+    if data.len() < 2 {
+        return Err(Error::InvalidLength);
+    }
+    let index = data[1] as usize;
+    if index >= SLOT_COUNT {
+        return Err(Error::InvalidValue);
+    }
+    match data[0] {
+        COMMAND_SELECT => {
+            let name = parse_text(&data[2..])?;
+            info!("[slots] storing next frame into slot {}", index);
+            *TARGET.lock().await = Some((index, name));
+            Ok(())
+        }
+        COMMAND_SHOW => show(index).await,
+        COMMAND_DELETE => {
+            let mut slots = SLOTS.lock().await;
+            slots[index] = None;
+            info!("[slots] deleted slot {}", index);
+            store_table(&slots).await
+        }
+        _ => Err(Error::InvalidValue),
+    }
+}
+
+/// Take the slot selected for the next committed frame, if any.
+pub async fn take_target() -> Option<(usize, String<SLOT_NAME_MAX_LEN>)> {
+    TARGET.lock().await.take()
+}
+
+/// Park the shown frame in flash before a frame for a slot is decoded into the display
+/// buffer, so it can be brought back by [`restore_shown`] once the frame is stored.
+///
+/// Called before every write into the display buffer during an upload, the frame is only
+/// parked once.
+pub async fn park_shown(display: &Display<'_>) -> Result<(), Error> {
+    if TARGET.lock().await.is_none() || PARKED.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    let mut storage = STORAGE.lock().await;
+    let storage = storage.as_mut().ok_or(Error::StorageUnavailable)?;
+    storage.write(SCRATCH_OFFSET, display.frame().buffer())?;
+    PARKED.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Load the frame parked by [`park_shown`] back into the display buffer, so the next
+/// refresh doesn't show the stored frame.
+pub async fn restore_shown(display: &mut Display<'_>) -> Result<(), Error> {
+    if !PARKED.swap(false, Ordering::Relaxed) {
+        return Ok(());
+    }
+    let mut storage = STORAGE.lock().await;
+    storage.as_mut().ok_or(Error::StorageUnavailable)?;
+    display
+        .frame_mut()
+        .load_buffer(mapped(SCRATCH_OFFSET, FRAME_LEN))?;
+    Ok(())
+}
+
+/// End the upload session owned by `conn`, once its frame has been committed or the
+/// upload was aborted.
+///
+/// A frame that wasn't committed is discarded: the frame parked for it is loaded back
+/// into the display buffer and the selected slot is dropped.
+pub async fn end_upload(conn: ConnHandle) {
+    // held so no other write sees the display buffer between releasing and restoring
+    let mut guard = DISPLAY.lock().await;
+    if !release_upload(conn) {
+        return;
+    }
+    if TARGET.lock().await.take().is_some() {
+        info!("[slots] upload aborted, slot selection dropped");
+    }
+    let result = match guard.as_mut() {
+        Some(display) => restore_shown(display).await,
+        // nothing can have been parked without a display
+        None => Ok(()),
+    };
+    if let Err(e) = result {
+        warn!("[slots] error restoring the shown frame: {:?}", e);
+    }
+}
+
+/// Store a frame buffer into a slot.
+pub async fn store(
+    index: usize,
+    name: String<SLOT_NAME_MAX_LEN>,
+    frame: &[u8],
+) -> Result<(), Error> {
+    {
+        let mut storage = STORAGE.lock().await;
+        let storage = storage.as_mut().ok_or(Error::StorageUnavailable)?;
+        storage.write(slot_offset(index), frame)?;
+    }
+    let mut slots = SLOTS.lock().await;
+    slots[index] = Some(Slot {
+        name,
+        hash: fnv1a(frame),
+    });
+    info!("[slots] stored frame in slot {}", index);
+    store_table(&slots).await
+}
+
+/// Load a stored image into the display buffer and refresh the display.
+pub async fn show(index: usize) -> Result<(), Error> {
+    if SLOTS.lock().await[index].is_none() {
+        return Err(Error::InvalidValue);
+    }
+    let mut guard = DISPLAY.lock().await;
+    let display = guard.as_mut().ok_or(Error::DisplayUnavailable)?;
+    // the display buffer holds the frame being uploaded
+    if upload_active() {
+        return Err(Error::UploadBusy);
+    }
+    {
+        // held so the slot isn't overwritten while it is copied
+        let mut storage = STORAGE.lock().await;
+        storage.as_mut().ok_or(Error::StorageUnavailable)?;
+        display
+            .frame_mut()
+            .load_buffer(mapped(slot_offset(index), FRAME_LEN))?;
+    }
+
+    info!("[slots] showing slot {}", index);
+    let image_hash = display.frame().hash();
+    widget::overlay(display).await;
+    let result = display.display_buffer();
+    record_refresh(image_hash, result.is_ok());
+    result?;
+    Ok(())
+}
+
+/// Show the slots selected for the slideshow in turn.
+#[embassy_executor::task]
+pub async fn slideshow_task() {
+    let mut next = 0;
+    loop {
+        let config = settings::get().await.slideshow;
+        let Some(interval) = config.interval else {
+            SLIDESHOW_CHANGED.wait().await;
+            continue;
+        };
+        if let Either::First(()) = select(SLIDESHOW_CHANGED.wait(), Timer::after(interval)).await {
+            continue;
+        }
+
+        let scheduled = schedule::active_slot().await.is_some();
+        let index = {
+            let slots = SLOTS.lock().await;
+            (next..next + SLOT_COUNT)
+                .map(|i| i % SLOT_COUNT)
+                .find(|&i| config.slots & (1 << i) != 0 && slots[i].is_some())
+        };
+        // scheduled images take precedence
+        let Some(index) = index.filter(|_| !scheduled) else {
+            continue;
+        };
+        next = index + 1;
+        if let Err(e) = show(index).await {
+            warn!("[slots] error showing slot {}: {:?}", index, e);
+        }
+    }
+}
+
+fn slot_offset(index: usize) -> u32 {
+    SLOT_DATA_OFFSET + index as u32 * SLOT_SIZE
+}
+
+async fn store_table(slots: &[Option<Slot>; SLOT_COUNT]) -> Result<(), Error> {
+    let mut table = [0u8; TABLE_LEN];
+    table[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+    for (slot, entry) in slots.iter().zip(table[4..].chunks_exact_mut(ENTRY_LEN)) {
+        if let Some(slot) = slot {
+            entry[0] = 1;
+            entry[1] = slot.name.len() as u8;
+            entry[2..2 + slot.name.len()].copy_from_slice(slot.name.as_bytes());
+            entry[18..22].copy_from_slice(&slot.hash.to_le_bytes());
+        }
+    }
+    let end = TABLE_LEN - 4;
+    let checksum = fnv1a(&table[..end]);
+    table[end..].copy_from_slice(&checksum.to_le_bytes());
+
+    let mut storage = STORAGE.lock().await;
+    let storage = storage.as_mut().ok_or(Error::StorageUnavailable)?;
+    storage.write(SLOT_TABLE_OFFSET, &table)
+}
@@ -0,0 +1,76 @@
+use core::cell::Cell;
+
+use embassy_sync::blocking_mutex::{Mutex, raw::CriticalSectionRawMutex};
+
+/// Firmware version as `[major, minor, patch]`
+pub const FIRMWARE_VERSION: [u8; 3] = [
+    parse_version(env!("CARGO_PKG_VERSION_MAJOR")),
+    parse_version(env!("CARGO_PKG_VERSION_MINOR")),
+    parse_version(env!("CARGO_PKG_VERSION_PATCH")),
+];
+
+/// The display could not be initialized
+pub const ERROR_DISPLAY_UNAVAILABLE: u8 = 1 << 0;
+/// The last display refresh failed
+pub const ERROR_REFRESH_FAILED: u8 = 1 << 1;
+/// The last image transfer failed
+pub const ERROR_TRANSFER_FAILED: u8 = 1 << 2;
+
+static DEVICE_STATE: Mutex<CriticalSectionRawMutex, Cell<DeviceState>> =
+    Mutex::new(Cell::new(DeviceState {
+        battery: None,
+        needs_update: true,
+        image_hash: 0,
+        error_flags: 0,
+    }));
+
+/// Summary of the device state, advertised so the hub can decide whether to connect.
+#[derive(Clone, Copy, defmt::Format)]
+pub struct DeviceState {
+    /// Battery level in percent, `None` until it has been measured
+    pub battery: Option<u8>,
+    /// Set until a frame from the hub has been displayed successfully
+    pub needs_update: bool,
+    /// Hash of the frame currently shown on the display
+    pub image_hash: u32,
+    /// Combination of the `ERROR_*` flags
+    pub error_flags: u8,
+}
+
+pub fn device_state() -> DeviceState {
+    DEVICE_STATE.lock(|state| state.get())
+}
+
+pub fn update_device_state(f: impl FnOnce(&mut DeviceState)) {
+    DEVICE_STATE.lock(|state| {
+        let mut value = state.get();
+        f(&mut value);
+        state.set(value);
+    })
+}
+
+/// Record the outcome of a display refresh showing the frame with `image_hash`.
+///
+/// A failed refresh keeps the hash of the frame that is still shown.
+pub fn record_refresh(image_hash: u32, success: bool) {
+    update_device_state(|state| {
+        state.needs_update = !success;
+        state.error_flags &= !ERROR_REFRESH_FAILED;
+        if success {
+            state.image_hash = image_hash;
+        } else {
+            state.error_flags |= ERROR_REFRESH_FAILED;
+        }
+    });
+}
+
+const fn parse_version(value: &str) -> u8 {
+    let bytes = value.as_bytes();
+    let mut result = 0u8;
+    let mut i = 0;
+    while i < bytes.len() {
+        result = result * 10 + (bytes[i] - b'0');
+        i += 1;
+    }
+    result
+}
@@ -0,0 +1,78 @@
+use embassy_rp::{
+    Peri,
+    flash::{Blocking, ERASE_SIZE, Flash},
+    peripherals::FLASH,
+};
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::Mutex};
+
+use crate::error::Error;
+
+/// Flash size of the Raspberry Pi Pico 2 W
+pub const FLASH_SIZE: usize = 4 * 1024 * 1024;
+/// The first 2 MiB are reserved for the firmware image, see `memory.x`
+const STORAGE_START: u32 = 2 * 1024 * 1024;
+/// Sector holding the persisted settings
+pub const SETTINGS_OFFSET: u32 = STORAGE_START;
+/// Sector holding the bonded centrals and their roles
+pub const BONDS_OFFSET: u32 = SETTINGS_OFFSET + ERASE_SIZE as u32;
+/// Sector holding the names and hashes of the stored images
+pub const SLOT_TABLE_OFFSET: u32 = BONDS_OFFSET + ERASE_SIZE as u32;
+/// Sector holding the schedule of the stored images
+pub const SCHEDULE_OFFSET: u32 = SLOT_TABLE_OFFSET + ERASE_SIZE as u32;
+/// Start of the stored images, leaving room for more small records in front of them
+pub const SLOT_DATA_OFFSET: u32 = STORAGE_START + 0x1_0000;
+/// Start of the uploaded fonts, behind the stored images
+pub const FONT_DATA_OFFSET: u32 = STORAGE_START + 0x10_0000;
+/// Frame parked while a frame for a slot is received, behind the uploaded fonts
+pub const SCRATCH_OFFSET: u32 = STORAGE_START + 0x14_0000;
+/// Frame parked while the panel is cleaned, which can happen while a frame is parked in
+/// [`SCRATCH_OFFSET`]
+pub const CLEAN_SCRATCH_OFFSET: u32 = SCRATCH_OFFSET + 0x2_0000;
+/// Flash is mapped into the address space from here on
+const XIP_BASE: usize = 0x1000_0000;
+
+pub static STORAGE: Mutex<CriticalSectionRawMutex, Option<Storage>> = Mutex::new(None);
+
+/// Persistent storage in the part of the flash not used by the firmware.
+pub struct Storage<'a> {
+    flash: Flash<'a, FLASH, Blocking, FLASH_SIZE>,
+}
+
+impl<'a> Storage<'a> {
+    pub fn new(flash: Peri<'a, FLASH>) -> Self {
+        Storage {
+            flash: Flash::new_blocking(flash),
+        }
+    }
+
+    pub fn read(&mut self, offset: u32, buf: &mut [u8]) -> Result<(), Error> {
+        self.flash.blocking_read(offset, buf)?;
+        Ok(())
+    }
+
+    /// Erase the sectors starting at `offset` needed to hold `len` bytes.
+    pub fn erase(&mut self, offset: u32, len: usize) -> Result<(), Error> {
+        let erase_end = offset + (len as u32).next_multiple_of(ERASE_SIZE as u32);
+        self.flash.blocking_erase(offset, erase_end)?;
+        Ok(())
+    }
+
+    /// Erase the sectors starting at `offset` needed to hold `data`, then write it.
+    pub fn write(&mut self, offset: u32, data: &[u8]) -> Result<(), Error> {
+        let erase_end = offset + (data.len() as u32).next_multiple_of(ERASE_SIZE as u32);
+        self.flash.blocking_erase(offset, erase_end)?;
+        self.flash.blocking_write(offset, data)?;
+        Ok(())
+    }
+}
+
+/// Read `len` bytes at `offset` directly through the memory mapped flash, e.g. for data
+/// used while drawing that is too large to copy into RAM.
+///
+/// The content changes when the region is written through [`Storage`], so callers have to
+/// check it is complete, e.g. by only writing its header last.
+pub fn mapped(offset: u32, len: usize) -> &'static [u8] {
+    // SAFETY: the storage region lies within the mapped flash and is never written through
+    // the mapping, flash operations invalidate the cache
+    unsafe { core::slice::from_raw_parts((XIP_BASE + offset as usize) as *const u8, len) }
+}
@@ -0,0 +1,104 @@
+use core::cell::Cell;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use defmt::{info, warn};
+use embassy_rp::adc::{self, Adc, Channel};
+use embassy_sync::blocking_mutex::{Mutex, raw::CriticalSectionRawMutex};
+use embassy_time::{Duration, Timer};
+
+use crate::bluetooth::transfer::upload_active;
+use crate::display::DISPLAY;
+use crate::error::Error;
+
+/// Range the panel is rated to refresh in, in 0.01 °C. The waveforms are tuned for room
+/// temperature, refreshing outside of it leaves faint or smeared images.
+const MIN_TEMPERATURE: i16 = 0;
+const MAX_TEMPERATURE: i16 = 50_00;
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+/// Samples averaged per measurement, a single conversion is noisy by a few degrees
+const SAMPLE_COUNT: u32 = 16;
+/// Reported while no measurement is available, as for the Bluetooth temperature characteristic
+pub const TEMPERATURE_UNKNOWN: i16 = i16::MIN;
+
+static TEMPERATURE: Mutex<CriticalSectionRawMutex, Cell<Option<i16>>> = Mutex::new(Cell::new(None));
+/// Set while the panel shows an older frame than the frame buffer, because a refresh was refused
+static REFRESH_REFUSED: AtomicBool = AtomicBool::new(false);
+
+/// Last measured temperature in 0.01 °C.
+pub fn current() -> Option<i16> {
+    TEMPERATURE.lock(|temperature| temperature.get())
+}
+
+/// Whether the panel may be refreshed at the current temperature, also if it is unknown.
+///
+/// The panel controller picks its waveform from its own sensor, which the driver doesn't
+/// let us override, so refreshes outside of the rated range are refused instead.
+fn refresh_allowed() -> bool {
+    current().is_none_or(|temperature| (MIN_TEMPERATURE..=MAX_TEMPERATURE).contains(&temperature))
+}
+
+/// Check the panel may be refreshed, remembering a refused refresh so it is caught up on
+/// once the temperature is back in range.
+pub fn begin_refresh() -> bool {
+    let allowed = refresh_allowed();
+    REFRESH_REFUSED.store(!allowed, Ordering::Relaxed);
+    allowed
+}
+
+/// Show the frame buffer if a refresh was refused while it was too cold or too hot.
+async fn catch_up() -> Result<(), Error> {
+    if !refresh_allowed() || !REFRESH_REFUSED.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    let mut guard = DISPLAY.lock().await;
+    let display = guard.as_mut().ok_or(Error::DisplayUnavailable)?;
+    // the display buffer holds the frame being uploaded, which gets shown once it is complete
+    if upload_active() {
+        return Ok(());
+    }
+    info!("[temperature] back in the panel's range, showing the current frame");
+    display.display_buffer()?;
+    Ok(())
+}
+
+/// Measure the temperature with the sensor built into the RP2350.
+#[embassy_executor::task]
+pub async fn temperature_task(mut adc: Adc<'static, adc::Blocking>, mut sensor: Channel<'static>) {
+    loop {
+        let mut sum = 0;
+        let mut result = Ok(());
+        for _ in 0..SAMPLE_COUNT {
+            match adc.blocking_read(&mut sensor) {
+                Ok(raw) => sum += u32::from(raw),
+                Err(e) => result = Err(e),
+            }
+        }
+        match result {
+            Ok(()) => {
+                let temperature = from_raw(sum / SAMPLE_COUNT);
+                if current().is_none() {
+                    info!("[temperature] {} (0.01 °C)", temperature);
+                }
+                TEMPERATURE.lock(|current| current.set(Some(temperature)));
+                if !refresh_allowed() {
+                    warn!(
+                        "[temperature] {} (0.01 °C) is outside of the panel's range",
+                        temperature
+                    );
+                }
+                if let Err(e) = catch_up().await {
+                    warn!("[temperature] error refreshing the display: {:?}", e);
+                }
+            }
+            Err(e) => warn!("[temperature] error reading the sensor: {:?}", e),
+        }
+        Timer::after(SAMPLE_INTERVAL).await;
+    }
+}
+
+/// Convert an ADC reading of the sensor to 0.01 °C, following the RP2350 datasheet:
+/// T = 27 - (V - 0.706) / 0.001721, with V measured against the 3.3 V reference.
+fn from_raw(raw: u32) -> i16 {
+    let microvolts = raw as i32 * 3_300_000 / 4096;
+    (27_00 - (microvolts - 706_000) * 100 / 1721) as i16
+}
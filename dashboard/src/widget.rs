@@ -0,0 +1,161 @@
+use core::fmt::Write;
+
+use defmt::{info, warn};
+use embassy_futures::select::{Either, select};
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::Mutex, signal::Signal};
+use embassy_time::{Duration, Timer};
+use embedded_graphics::{
+    mono_font::MonoTextStyleBuilder,
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{Baseline, Text},
+};
+use epd_waveshare::color::TriColor;
+use heapless::String;
+use periphery_render::frame::Covered;
+
+use crate::bluetooth::transfer::upload_active;
+use crate::clock::{self, LocalTime};
+use crate::display::{DISPLAY, Display};
+use crate::error::Error;
+use crate::settings;
+
+pub use periphery_render::widget::{WidgetConfig, WidgetMode};
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+/// Longest text drawn by the widget, "Mon 2026-10-19 14:05"
+const TEXT_MAX_LEN: usize = 20;
+
+/// Pixels under the widget, two bits each, for the large font and the longest text
+const COVERED_LEN: usize = TEXT_MAX_LEN * 10 * 20 / 4;
+
+/// Part of the image under the widget, so the widget isn't part of the image and can be
+/// removed or moved
+static COVERED: Mutex<CriticalSectionRawMutex, Covered<COVERED_LEN>> = Mutex::new(Covered::new());
+/// Signaled when the widget settings or the clock changed, so the widget is redrawn
+pub static WIDGET_CHANGED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Draw the widget on top of a new image in the frame buffer.
+///
+/// Called before every refresh of a new image, so the widget stays on top of it.
+pub async fn overlay(display: &mut Display<'_>) {
+    let mut covered = COVERED.lock().await;
+    // the pixels kept belong to the previous image
+    covered.forget();
+    let config = settings::get().await.widget;
+    if let Some(text) = clock::local_time().and_then(|time| text(&config, time))
+        && let Err(e) = draw(display, &mut covered, &config, &text)
+    {
+        warn!("[widget] error drawing: {:?}", e);
+    }
+}
+
+/// Keep the widget up to date, redrawing it whenever its text changes.
+#[embassy_executor::task]
+pub async fn widget_task() {
+    let mut shown: Option<String<TEXT_MAX_LEN>> = None;
+    let mut changed = false;
+    loop {
+        let config = settings::get().await.widget;
+        let text = clock::local_time().and_then(|time| text(&config, time));
+        // redraw even if the text stayed the same, e.g. after the widget moved
+        if text != shown || changed {
+            match redraw(&config, text.as_deref()).await {
+                Ok(()) => {
+                    shown = text;
+                    changed = false;
+                }
+                Err(e) => warn!("[widget] error updating: {:?}", e),
+            }
+        }
+
+        // wake up at the start of the next minute
+        let seconds = clock::local_time().map_or(0, |time| time.seconds);
+        let delay = Duration::from_secs(60 - u64::from(seconds));
+        if let Either::First(()) = select(WIDGET_CHANGED.wait(), Timer::after(delay)).await {
+            changed = true;
+        }
+    }
+}
+
+/// Show `text` instead of the widget drawn before, or only the image if `None`.
+async fn redraw(config: &WidgetConfig, text: Option<&str>) -> Result<(), Error> {
+    let mut guard = DISPLAY.lock().await;
+    let display = guard.as_mut().ok_or(Error::DisplayUnavailable)?;
+    // the display buffer holds the frame being uploaded
+    if upload_active() {
+        return Err(Error::UploadBusy);
+    }
+    let mut covered = COVERED.lock().await;
+    display.frame_mut().uncover(&mut covered);
+    match text {
+        Some(text) => {
+            draw(display, &mut covered, config, text)?;
+            info!("[widget] showing {}", text);
+        }
+        None => info!("[widget] hidden"),
+    }
+    // only the window around the widget is refreshed, unless it is chromatic
+    display.display_buffer()?;
+    Ok(())
+}
+
+/// Draw `text` on a white box, keeping the pixels it covers.
+fn draw(
+    display: &mut Display<'_>,
+    covered: &mut Covered<COVERED_LEN>,
+    config: &WidgetConfig,
+    text: &str,
+) -> Result<(), Error> {
+    let font = config.font();
+    let color = if config.chromatic {
+        TriColor::Chromatic
+    } else {
+        TriColor::Black
+    };
+    let area = Rectangle::new(
+        config.position,
+        Size::new(
+            font.character_size.width * text.len() as u32,
+            font.character_size.height,
+        ),
+    );
+    display.frame().cover(covered, area)?;
+    let mut canvas = display.frame_mut().canvas();
+    let Ok(()) = area
+        .into_styled(PrimitiveStyle::with_fill(TriColor::White))
+        .draw(&mut canvas);
+    let style = MonoTextStyleBuilder::new()
+        .font(font)
+        .text_color(color)
+        .build();
+    let Ok(_) = Text::with_baseline(text, config.position, style, Baseline::Top).draw(&mut canvas);
+    Ok(())
+}
+
+/// Text shown by the widget at `time`, `None` if the widget is off.
+fn text(config: &WidgetConfig, time: LocalTime) -> Option<String<TEXT_MAX_LEN>> {
+    let minutes = if config.hourly {
+        time.minutes - time.minutes % 60
+    } else {
+        time.minutes
+    };
+    let mut text = String::new();
+    let date = |text: &mut String<TEXT_MAX_LEN>| {
+        write!(
+            text,
+            "{} {:04}-{:02}-{:02}",
+            WEEKDAYS[time.weekday as usize], time.year, time.month, time.day
+        )
+    };
+    let result = match config.mode {
+        WidgetMode::Off => return None,
+        WidgetMode::Time => write!(text, "{:02}:{:02}", minutes / 60, minutes % 60),
+        WidgetMode::Date => date(&mut text),
+        WidgetMode::DateTime => {
+            date(&mut text).and_then(|()| write!(text, " {:02}:{:02}", minutes / 60, minutes % 60))
+        }
+    };
+    result.ok()?;
+    Some(text)
+}
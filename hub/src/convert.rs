@@ -0,0 +1,91 @@
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, Rgb, RgbImage};
+use periphery_protocol::chunk::Color;
+
+/// Colors shown by the panel, approximately, used to pick the closest color per pixel
+pub const PALETTE: [(Color, [u8; 3]); 3] = [
+    (Color::White, [0xFF, 0xFF, 0xFF]),
+    (Color::Black, [0x00, 0x00, 0x00]),
+    (Color::Chromatic, [0xC0, 0x10, 0x10]),
+];
+
+/// How images with another aspect ratio than the frame are resized
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Fit {
+    /// Fill the whole frame, cropping the overlapping sides
+    #[default]
+    Cover,
+    /// Show the whole image, centered on a white frame
+    Contain,
+}
+
+/// Resize `image` to `width` x `height` and dither it to the [`PALETTE`], returning the
+/// pixels row by row.
+pub fn convert(image: &DynamicImage, width: u32, height: u32, fit: Fit) -> Vec<Color> {
+    let resized = match fit {
+        Fit::Cover => image.resize_to_fill(width, height, FilterType::Triangle),
+        Fit::Contain => image.resize(width, height, FilterType::Triangle),
+    };
+    let mut frame = RgbImage::from_pixel(width, height, Rgb([0xFF; 3]));
+    let x = (width - resized.width()) / 2;
+    let y = (height - resized.height()) / 2;
+    imageops::overlay(&mut frame, &resized.to_rgb8(), x.into(), y.into());
+    dither(&frame)
+}
+
+/// Floyd-Steinberg dithering of `image` to the [`PALETTE`], returning the pixels row by
+/// row.
+///
+/// Colors are compared by luma and redness rather than in RGB, where the dark red of the
+/// panel would be the closest match for mid grays.
+pub fn dither(image: &RgbImage) -> Vec<Color> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let palette = PALETTE.map(|(color, rgb)| (color, to_luma_redness(rgb.map(f32::from))));
+    let mut values: Vec<[f32; 2]> = image
+        .pixels()
+        .map(|Rgb(rgb)| to_luma_redness(rgb.map(f32::from)))
+        .collect();
+    let mut colors = Vec::with_capacity(values.len());
+    for y in 0..height {
+        for x in 0..width {
+            // clamped, so the error of colors outside the palette doesn't pile up
+            let [luma, redness] = values[y * width + x];
+            let value = [luma.clamp(0.0, 255.0), redness.clamp(-127.5, 255.0)];
+            let (color, target) = closest(&palette, value);
+            colors.push(color);
+
+            let error = [value[0] - target[0], value[1] - target[1]];
+            let mut spread = |dx: isize, dy: usize, weight: f32| {
+                let Some(x) = x.checked_add_signed(dx).filter(|&x| x < width) else {
+                    return;
+                };
+                if y + dy < height {
+                    let value = &mut values[(y + dy) * width + x];
+                    value[0] += error[0] * weight;
+                    value[1] += error[1] * weight;
+                }
+            };
+            spread(1, 0, 7.0 / 16.0);
+            spread(-1, 1, 3.0 / 16.0);
+            spread(0, 1, 5.0 / 16.0);
+            spread(1, 1, 1.0 / 16.0);
+        }
+    }
+    colors
+}
+
+/// Luma and how much red outweighs green and blue, both in the range of the channels.
+fn to_luma_redness([r, g, b]: [f32; 3]) -> [f32; 2] {
+    [0.299 * r + 0.587 * g + 0.114 * b, r - (g + b) / 2.0]
+}
+
+/// Palette entry closest to `value`.
+fn closest(palette: &[(Color, [f32; 2]); 3], value: [f32; 2]) -> (Color, [f32; 2]) {
+    let distance =
+        |target: &[f32; 2]| (value[0] - target[0]).powi(2) + (value[1] - target[1]).powi(2);
+    palette
+        .iter()
+        .copied()
+        .min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)))
+        .unwrap_or(palette[0])
+}
@@ -0,0 +1,20 @@
+use periphery_protocol::Error;
+use periphery_protocol::chunk::{self, CHUNK_LEN, CHUNK_PIXELS, Color, PIXEL_COUNT};
+use periphery_protocol::compression;
+
+/// Pack the pixels of a whole frame, row by row as seen by the hub, into the chunks
+/// written to the dashboard.
+pub fn encode(pixels: &[Color]) -> Result<Vec<u8>, Error> {
+    if pixels.len() != PIXEL_COUNT as usize {
+        return Err(Error::InvalidLength);
+    }
+    let (chunks, _) = pixels.as_chunks::<CHUNK_PIXELS>();
+    Ok(chunks.iter().flat_map(chunk::encode).collect())
+}
+
+/// Compress an encoded frame for the compressed L2CAP channel.
+pub fn compress(encoded: &[u8]) -> Vec<u8> {
+    let mut compressed = Vec::with_capacity(encoded.len() / CHUNK_LEN);
+    compression::compress(encoded, |piece| compressed.extend_from_slice(piece));
+    compressed
+}
@@ -0,0 +1,10 @@
+//! Reference hub for the dashboard: converts images into the frames the firmware
+//! expects and uploads them.
+//!
+//! Images are resized and dithered to the panel's colors by [`convert`], packed into
+//! chunks by [`encode`] and sent through a [`transport::Transport`], which connects the
+//! hub to the dashboard's GATT server.
+
+pub mod convert;
+pub mod encode;
+pub mod transport;
@@ -0,0 +1,110 @@
+//! Convert an image for the dashboard and write it to a file or upload it.
+//!
+//! ```text
+//! periphery_hub [--portrait] [--contain] [--output FILE] [--compress]
+//!     [--upload-with COMMAND] IMAGE
+//! ```
+//!
+//! - `--portrait`: the panel is mounted in portrait, so frames are 480 pixels wide
+//! - `--contain`: show the whole image on white instead of cropping it to fill the frame
+//! - `--output`: write the encoded frame, as written to `write_buffer`
+//! - `--compress`: compress the written frame, as sent on the compressed L2CAP channel
+//! - `--upload-with`: upload through a helper started with `sh -c COMMAND`, see
+//!   `ProcessTransport`
+
+use std::fs;
+use std::process::{Command, ExitCode};
+
+use periphery_hub::convert::{self, Fit};
+use periphery_hub::encode;
+use periphery_hub::transport::{self, ProcessTransport};
+
+#[derive(Default)]
+struct Options {
+    portrait: bool,
+    fit: Fit,
+    output: Option<String>,
+    compress: bool,
+    upload_with: Option<String>,
+    image: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let options = match parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{e}");
+            eprintln!(
+                "usage: periphery_hub [--portrait] [--contain] [--output FILE] [--compress] \
+                 [--upload-with COMMAND] IMAGE"
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {arg}"));
+        match arg.as_str() {
+            "--portrait" => options.portrait = true,
+            "--contain" => options.fit = Fit::Contain,
+            "--output" => options.output = Some(value()?),
+            "--compress" => options.compress = true,
+            "--upload-with" => options.upload_with = Some(value()?),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ => options.image = Some(arg),
+        }
+    }
+    if options.image.is_none() {
+        return Err("missing image".into());
+    }
+    if options.output.is_none() && options.upload_with.is_none() {
+        return Err("nothing to do, pass --output or --upload-with".into());
+    }
+    Ok(options)
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let path = options.image.as_deref().unwrap_or_default();
+    let image = image::open(path).map_err(|e| format!("reading {path}: {e}"))?;
+    let (width, height) = if options.portrait {
+        (480, 800)
+    } else {
+        (800, 480)
+    };
+    let pixels = convert::convert(&image, width, height, options.fit);
+    let encoded = encode::encode(&pixels).map_err(|e| format!("encoding {path}: {e:?}"))?;
+
+    if let Some(output) = &options.output {
+        let data = if options.compress {
+            encode::compress(&encoded)
+        } else {
+            encoded.clone()
+        };
+        fs::write(output, &data).map_err(|e| format!("writing {output}: {e}"))?;
+        println!("{} bytes written to {output}", data.len());
+    }
+
+    if let Some(helper) = &options.upload_with {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(helper);
+        let mut transport =
+            ProcessTransport::spawn(command).map_err(|e| format!("starting {helper}: {e}"))?;
+        let status = transport::upload(&mut transport, &encoded)
+            .map_err(|e| format!("uploading {path}: {e}"))?;
+        transport
+            .close()
+            .map_err(|e| format!("closing {helper}: {e}"))?;
+        println!("uploaded, {status:?}");
+    }
+    Ok(())
+}
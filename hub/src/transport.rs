@@ -0,0 +1,189 @@
+//! Uploading encoded frames to the dashboard's GATT server.
+//!
+//! The hub writes the chunks to `write_buffer`, which places them at the position of the
+//! cursor characteristic and advances it, and commits the frame by writing 1 to `write`.
+//! The outcome is read from the status characteristic afterwards.
+//!
+//! How the characteristics are reached is up to the [`Transport`], so any Bluetooth stack
+//! can be plugged in, e.g. through an external helper with [`ProcessTransport`].
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, ExitStatus, Stdio};
+
+use periphery_protocol::chunk::CHUNK_LEN;
+use periphery_protocol::gatt::dashboard;
+use periphery_protocol::status::TransferStatus;
+use periphery_protocol::transfer::MAX_WRITE_LEN;
+
+/// Number of whole chunks written to `write_buffer` at once
+const WRITE_CHUNKS: usize = MAX_WRITE_LEN / CHUNK_LEN;
+
+/// Access to the characteristics of a connected dashboard.
+pub trait Transport {
+    type Error;
+
+    /// Read the value of `characteristic`.
+    fn read(&mut self, characteristic: u128) -> Result<Vec<u8>, Self::Error>;
+
+    /// Write `value` to `characteristic`, waiting for the response.
+    fn write(&mut self, characteristic: u128, value: &[u8]) -> Result<(), Self::Error>;
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum UploadError<E> {
+    /// The encoded frame doesn't consist of whole chunks
+    InvalidLength,
+    /// Accessing a characteristic failed
+    Transport(E),
+    /// The status characteristic holds an invalid value
+    Protocol(periphery_protocol::Error),
+    /// The dashboard reported the error `code`, see the firmware's `Error::code`
+    Dashboard { code: u8 },
+}
+
+impl<E: fmt::Display> fmt::Display for UploadError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UploadError::InvalidLength => write!(f, "the frame doesn't consist of whole chunks"),
+            UploadError::Transport(e) => write!(f, "{e}"),
+            UploadError::Protocol(e) => write!(f, "invalid status: {e:?}"),
+            UploadError::Dashboard { code } => write!(f, "dashboard reported error {code:#04x}"),
+        }
+    }
+}
+
+/// Upload the `encoded` frame and display it, returning the final transfer status,
+/// [`TransferStatus::Done`] or [`TransferStatus::Unchanged`].
+pub fn upload<T: Transport>(
+    transport: &mut T,
+    encoded: &[u8],
+) -> Result<TransferStatus, UploadError<T::Error>> {
+    if encoded.is_empty() || !encoded.len().is_multiple_of(CHUNK_LEN) {
+        return Err(UploadError::InvalidLength);
+    }
+    for chunks in encoded.chunks(WRITE_CHUNKS * CHUNK_LEN) {
+        transport
+            .write(dashboard::WRITE_BUFFER, chunks)
+            .map_err(UploadError::Transport)?;
+    }
+    let committed = transport.write(dashboard::WRITE, &[1]);
+
+    // a rejected commit still leaves the reason in the status characteristic
+    let status = transport
+        .read(dashboard::STATUS)
+        .map_err(UploadError::Transport)?;
+    match TransferStatus::decode(&status).map_err(UploadError::Protocol)? {
+        TransferStatus::Error { code } => Err(UploadError::Dashboard { code }),
+        status => committed.map(|()| status).map_err(UploadError::Transport),
+    }
+}
+
+/// UUID in its usual form, e.g. `00010001-50bf-48a2-9d8a-835aaa2fb179`.
+pub fn format_uuid(uuid: u128) -> String {
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        uuid >> 96,
+        uuid >> 80 & 0xFFFF,
+        uuid >> 64 & 0xFFFF,
+        uuid >> 48 & 0xFFFF,
+        uuid & 0xFFFF_FFFF_FFFF
+    )
+}
+
+/// Transport handing the characteristic accesses to a helper process, which talks to
+/// the dashboard with whatever Bluetooth stack is at hand.
+///
+/// Requests are written to the helper's stdin one per line, values hex encoded:
+/// - `read <uuid>`, answered with `ok <value>`
+/// - `write <uuid> <value>`, answered with `ok`
+///
+/// Failed requests are answered with `error <message>`.
+pub struct ProcessTransport {
+    child: Child,
+    requests: BufWriter<ChildStdin>,
+    responses: BufReader<ChildStdout>,
+}
+
+impl ProcessTransport {
+    pub fn spawn(mut command: Command) -> io::Result<Self> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(io::Error::other("helper without stdin or stdout"));
+        };
+        Ok(ProcessTransport {
+            child,
+            requests: BufWriter::new(stdin),
+            responses: BufReader::new(stdout),
+        })
+    }
+
+    /// Close the helper's stdin, telling it to disconnect, and wait for it to exit.
+    pub fn close(self) -> io::Result<ExitStatus> {
+        let ProcessTransport {
+            mut child,
+            requests,
+            responses: _,
+        } = self;
+        requests.into_inner().map_err(|e| e.into_error())?;
+        child.wait()
+    }
+
+    /// Send `request` and return the value of the helper's `ok` response.
+    fn request(&mut self, request: &str) -> io::Result<Vec<u8>> {
+        writeln!(self.requests, "{request}")?;
+        self.requests.flush()?;
+        let mut response = String::new();
+        if self.responses.read_line(&mut response)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "helper exited",
+            ));
+        }
+        let response = response.trim_end();
+        match response.split_once(' ').unwrap_or((response, "")) {
+            ("ok", value) => decode_hex(value),
+            ("error", message) => Err(io::Error::other(message.to_string())),
+            _ => Err(invalid_response(response)),
+        }
+    }
+}
+
+impl Transport for ProcessTransport {
+    type Error = io::Error;
+
+    fn read(&mut self, characteristic: u128) -> io::Result<Vec<u8>> {
+        self.request(&format!("read {}", format_uuid(characteristic)))
+    }
+
+    fn write(&mut self, characteristic: u128, value: &[u8]) -> io::Result<()> {
+        let value: String = value.iter().map(|byte| format!("{byte:02x}")).collect();
+        self.request(&format!("write {} {value}", format_uuid(characteristic)))?;
+        Ok(())
+    }
+}
+
+fn decode_hex(value: &str) -> io::Result<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return Err(invalid_response(value));
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| {
+            value
+                .get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| invalid_response(value))
+        })
+        .collect()
+}
+
+fn invalid_response(response: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid response from helper: {response}"),
+    )
+}
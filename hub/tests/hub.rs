@@ -0,0 +1,279 @@
+use std::process::Command;
+
+use embedded_graphics::prelude::*;
+use epd_waveshare::color::TriColor;
+use image::{DynamicImage, Rgb, RgbImage};
+use periphery_hub::convert::{self, Fit};
+use periphery_hub::encode;
+use periphery_hub::transport::{self, ProcessTransport, Transport, UploadError, format_uuid};
+use periphery_protocol::Error;
+use periphery_protocol::chunk::{CHUNK_LEN, CHUNK_PIXELS, Color, PIXEL_COUNT};
+use periphery_protocol::compression::Decompressor;
+use periphery_protocol::gatt::dashboard;
+use periphery_protocol::status::TransferStatus;
+use periphery_render::frame::{OrientationConfig, Rotation};
+use periphery_render::screen::Screen;
+use periphery_simulator::SimulatedPanel;
+
+const WHITE: Rgb<u8> = Rgb([0xFF, 0xFF, 0xFF]);
+const BLACK: Rgb<u8> = Rgb([0x00, 0x00, 0x00]);
+const RED: Rgb<u8> = Rgb([0xFF, 0x00, 0x00]);
+
+/// The dashboard's `write_buffer` handshake, decoding chunks like the firmware
+struct Dashboard {
+    screen: Screen<SimulatedPanel>,
+    cursor: u32,
+    status: TransferStatus,
+}
+
+impl Dashboard {
+    fn new(orientation: OrientationConfig) -> Self {
+        let mut screen = Screen::new(SimulatedPanel::default());
+        screen.frame_mut().set_orientation(orientation);
+        Dashboard {
+            screen,
+            cursor: 0,
+            status: TransferStatus::Idle,
+        }
+    }
+
+    /// Color shown at `point` as seen by the hub
+    fn pixel(&self, point: Point) -> TriColor {
+        let orientation = self.screen.frame().orientation();
+        self.screen.panel().pixel(orientation.to_physical(point))
+    }
+}
+
+impl Transport for Dashboard {
+    type Error = Error;
+
+    fn read(&mut self, characteristic: u128) -> Result<Vec<u8>, Error> {
+        match characteristic {
+            dashboard::STATUS => Ok(self.status.encode().to_vec()),
+            dashboard::CURSOR => Ok(self.cursor.to_le_bytes().to_vec()),
+            _ => Err(Error::InvalidValue),
+        }
+    }
+
+    fn write(&mut self, characteristic: u128, value: &[u8]) -> Result<(), Error> {
+        match characteristic {
+            dashboard::WRITE_BUFFER => {
+                let (chunks, remainder) = value.as_chunks::<CHUNK_LEN>();
+                if chunks.is_empty() || !remainder.is_empty() {
+                    return Err(Error::InvalidLength);
+                }
+                for chunk in chunks {
+                    if let Err(e) = self.screen.frame_mut().write_to_buffer(chunk, self.cursor) {
+                        self.status = TransferStatus::Error { code: 0x03 };
+                        return Err(e);
+                    }
+                    self.cursor += 1;
+                }
+                self.status = TransferStatus::Receiving {
+                    bytes: self.cursor * CHUNK_LEN as u32,
+                };
+                Ok(())
+            }
+            dashboard::WRITE => {
+                self.cursor = 0;
+                let Ok(refresh) = self.screen.display_buffer();
+                self.status = match refresh {
+                    periphery_render::screen::Refresh::Unchanged => TransferStatus::Unchanged,
+                    _ => TransferStatus::Done,
+                };
+                Ok(())
+            }
+            _ => Err(Error::InvalidValue),
+        }
+    }
+}
+
+/// Image with the left half black, the right half red, and a white square in the middle
+fn test_image(width: u32, height: u32) -> DynamicImage {
+    let image = RgbImage::from_fn(width, height, |x, y| {
+        let (dx, dy) = (x.abs_diff(width / 2), y.abs_diff(height / 2));
+        if dx < 40 && dy < 40 {
+            WHITE
+        } else if x < width / 2 {
+            BLACK
+        } else {
+            RED
+        }
+    });
+    DynamicImage::ImageRgb8(image)
+}
+
+#[test]
+fn solid_colors_are_kept() {
+    let pixels = convert::convert(&test_image(800, 480), 800, 480, Fit::Cover);
+    assert_eq!(pixels.len(), PIXEL_COUNT as usize);
+    assert_eq!(pixels[0], Color::Black);
+    assert_eq!(pixels[799], Color::Chromatic);
+    assert_eq!(pixels[240 * 800 + 400], Color::White);
+}
+
+#[test]
+fn gray_is_dithered() {
+    let gray = RgbImage::from_pixel(64, 64, Rgb([0x80; 3]));
+    let pixels = convert::dither(&gray);
+    let black = pixels
+        .iter()
+        .filter(|&&color| color == Color::Black)
+        .count();
+    let white = pixels
+        .iter()
+        .filter(|&&color| color == Color::White)
+        .count();
+    assert_eq!(black + white, pixels.len());
+    assert!((black as f32 / pixels.len() as f32 - 0.5).abs() < 0.05);
+}
+
+#[test]
+fn images_are_resized() {
+    // twice the frame's width, so covering crops the sides
+    let image = DynamicImage::ImageRgb8(RgbImage::from_fn(1600, 480, |x, _| {
+        if (400..1200).contains(&x) { BLACK } else { RED }
+    }));
+    let covered = convert::convert(&image, 800, 480, Fit::Cover);
+    assert!(covered.iter().all(|&color| color == Color::Black));
+
+    let contained = convert::convert(&image, 800, 480, Fit::Contain);
+    assert_eq!(contained[0], Color::White);
+    assert_eq!(contained[240 * 800], Color::Chromatic);
+    assert_eq!(contained[240 * 800 + 400], Color::Black);
+}
+
+#[test]
+fn frames_need_every_pixel() {
+    assert_eq!(
+        encode::encode(&[Color::White; CHUNK_PIXELS]),
+        Err(Error::InvalidLength)
+    );
+}
+
+#[test]
+fn uploaded_frames_are_shown() {
+    let image = test_image(800, 480);
+    let pixels = convert::convert(&image, 800, 480, Fit::Cover);
+    let encoded = encode::encode(&pixels).unwrap();
+    let mut dashboard = Dashboard::new(OrientationConfig::default());
+    assert_eq!(
+        transport::upload(&mut dashboard, &encoded),
+        Ok(TransferStatus::Done)
+    );
+
+    assert_eq!(dashboard.screen.panel().full_refreshes(), 1);
+    for (index, &color) in pixels.iter().enumerate() {
+        let point = Point::new(index as i32 % 800, index as i32 / 800);
+        let expected = match color {
+            Color::Black => TriColor::Black,
+            Color::White => TriColor::White,
+            Color::Chromatic => TriColor::Chromatic,
+        };
+        assert_eq!(dashboard.pixel(point), expected, "{point:?}");
+    }
+
+    // the same frame again doesn't need a refresh
+    assert_eq!(
+        transport::upload(&mut dashboard, &encoded),
+        Ok(TransferStatus::Unchanged)
+    );
+}
+
+#[test]
+fn portrait_frames_are_shown_upright() {
+    let pixels = convert::convert(&test_image(480, 800), 480, 800, Fit::Cover);
+    let encoded = encode::encode(&pixels).unwrap();
+    let mut dashboard = Dashboard::new(OrientationConfig {
+        rotation: Rotation::Rotate90,
+        mirrored: false,
+    });
+    transport::upload(&mut dashboard, &encoded).unwrap();
+
+    assert_eq!(dashboard.pixel(Point::new(0, 0)), TriColor::Black);
+    assert_eq!(dashboard.pixel(Point::new(479, 799)), TriColor::Chromatic);
+    assert_eq!(dashboard.pixel(Point::new(240, 400)), TriColor::White);
+}
+
+#[test]
+fn dashboard_errors_are_reported() {
+    let mut dashboard = Dashboard::new(OrientationConfig::default());
+    // one chunk more than the frame holds
+    let encoded = vec![0xFF; (PIXEL_COUNT as usize / CHUNK_PIXELS + 1) * CHUNK_LEN];
+    assert!(matches!(
+        transport::upload(&mut dashboard, &encoded),
+        Err(UploadError::Transport(Error::CursorOutOfRange))
+    ));
+    assert!(matches!(
+        transport::upload(&mut dashboard, &[0; 10]),
+        Err(UploadError::InvalidLength)
+    ));
+}
+
+#[test]
+fn black_and_white_frames_compress_well() {
+    let image = DynamicImage::ImageRgb8(RgbImage::from_fn(800, 480, |x, _| {
+        if x < 100 { BLACK } else { WHITE }
+    }));
+    let pixels = convert::convert(&image, 800, 480, Fit::Cover);
+    let encoded = encode::encode(&pixels).unwrap();
+    assert!(encode::compress(&encoded).len() < encoded.len() / 20);
+}
+
+#[test]
+fn compressed_frames_decompress_to_the_encoded_frame() {
+    let pixels = convert::convert(&test_image(800, 480), 800, 480, Fit::Cover);
+    let encoded = encode::encode(&pixels).unwrap();
+    let compressed = encode::compress(&encoded);
+    assert!(compressed.len() < encoded.len());
+
+    let mut decompressor = Decompressor::new();
+    let mut decompressed = Vec::new();
+    for sdu in compressed.chunks(1024) {
+        decompressor
+            .feed::<()>(sdu, |data| {
+                decompressed.extend_from_slice(data);
+                Ok(())
+            })
+            .unwrap();
+    }
+    assert!(decompressor.is_idle());
+    assert_eq!(decompressed, encoded);
+}
+
+#[test]
+fn uuids_are_formatted() {
+    assert_eq!(
+        format_uuid(dashboard::WRITE_BUFFER),
+        "00010001-50bf-48a2-9d8a-835aaa2fb179"
+    );
+}
+
+/// Helper answering every read with `status` and accepting every write
+fn helper(status: TransferStatus) -> Command {
+    let status: String = status.encode().iter().map(|b| format!("{b:02x}")).collect();
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(format!(
+        "while read request uuid value; do \
+            if [ $request = read ]; then echo ok {status}; else echo ok; fi; \
+        done"
+    ));
+    command
+}
+
+#[test]
+fn helper_processes_transport_requests() {
+    let mut transport = ProcessTransport::spawn(helper(TransferStatus::Done)).unwrap();
+    assert!(matches!(
+        transport::upload(&mut transport, &[0xFF; 3 * CHUNK_LEN]),
+        Ok(TransferStatus::Done)
+    ));
+    assert!(transport.close().unwrap().success());
+
+    let mut transport =
+        ProcessTransport::spawn(helper(TransferStatus::Error { code: 0x0A })).unwrap();
+    assert!(matches!(
+        transport::upload(&mut transport, &[0xFF; CHUNK_LEN]),
+        Err(UploadError::Dashboard { code: 0x0A })
+    ));
+}
@@ -0,0 +1,146 @@
+use crate::Error;
+
+/// Permissions granted to a bonded central, combinable as flags.
+///
+/// Centrals without any role (including all unbonded ones) can only read.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Roles(u8);
+
+impl Roles {
+    pub const VIEWER: Roles = Roles(0);
+    /// May upload and display images
+    pub const HUB: Roles = Roles(1 << 0);
+    /// May change settings and assign roles
+    pub const ADMIN: Roles = Roles(1 << 1);
+
+    pub fn contains(&self, other: Roles) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    pub fn from_bits(bits: u8) -> Result<Self, Error> {
+        if bits & !(Self::HUB.0 | Self::ADMIN.0) != 0 {
+            return Err(Error::InvalidValue);
+        }
+        Ok(Roles(bits))
+    }
+}
+
+impl core::ops::BitOr for Roles {
+    type Output = Roles;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Roles(self.0 | rhs.0)
+    }
+}
+
+/// Roles an admin assigns to a bonded central, written to the roles characteristic.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RoleAssignment {
+    /// Identity address of the central
+    pub address: [u8; 6],
+    pub roles: Roles,
+}
+
+impl RoleAssignment {
+    /// Encoded as `[identity address (6 bytes), roles]`.
+    pub const ENCODED_LEN: usize = 7;
+
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut encoded = [0u8; Self::ENCODED_LEN];
+        encoded[0..6].copy_from_slice(&self.address);
+        encoded[6] = self.roles.0;
+        encoded
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let data: &[u8; Self::ENCODED_LEN] = data.try_into().map_err(|_| Error::InvalidLength)?;
+        let mut address = [0u8; 6];
+        address.copy_from_slice(&data[0..6]);
+        Ok(RoleAssignment {
+            address,
+            roles: Roles::from_bits(data[6])?,
+        })
+    }
+}
+
+/// Roles of a central bonding while `bonded` other centrals are remembered. The first
+/// bonded central becomes hub and admin, later ones start without any role until an admin
+/// assigns one.
+pub fn initial_roles(bonded: usize) -> Roles {
+    if bonded == 0 {
+        Roles::HUB | Roles::ADMIN
+    } else {
+        Roles::VIEWER
+    }
+}
+
+/// Bond forgotten to make room in a full table, given the roles of the bonds from the
+/// oldest to the newest.
+///
+/// Admins are kept so the device can still be managed, `None` if only admins are left.
+pub fn evicted_bond(roles: impl IntoIterator<Item = Roles>) -> Option<usize> {
+    roles
+        .into_iter()
+        .position(|roles| !roles.contains(Roles::ADMIN))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roles_combine() {
+        let roles = Roles::HUB | Roles::ADMIN;
+        assert!(roles.contains(Roles::HUB));
+        assert!(roles.contains(Roles::ADMIN));
+        assert!(roles.contains(Roles::VIEWER));
+        assert!(!Roles::HUB.contains(Roles::ADMIN));
+        assert!(!Roles::VIEWER.contains(Roles::HUB));
+        assert_eq!(Roles::from_bits(roles.bits()), Ok(roles));
+        assert_eq!(Roles::from_bits(1 << 2), Err(Error::InvalidValue));
+    }
+
+    #[test]
+    fn assignment_round_trip() {
+        let assignment = RoleAssignment {
+            address: [1, 2, 3, 4, 5, 6],
+            roles: Roles::HUB,
+        };
+        assert_eq!(assignment.encode(), [1, 2, 3, 4, 5, 6, 1]);
+        assert_eq!(RoleAssignment::decode(&assignment.encode()), Ok(assignment));
+        assert_eq!(
+            RoleAssignment::decode(&[1, 2, 3, 4, 5, 6, 0x80]),
+            Err(Error::InvalidValue)
+        );
+        assert_eq!(
+            RoleAssignment::decode(&[1, 2, 3]),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn first_bond_becomes_admin() {
+        assert_eq!(initial_roles(0), Roles::HUB | Roles::ADMIN);
+        assert_eq!(initial_roles(1), Roles::VIEWER);
+    }
+
+    #[test]
+    fn admins_are_not_evicted() {
+        let admin = Roles::HUB | Roles::ADMIN;
+        assert_eq!(
+            evicted_bond([Roles::HUB, Roles::VIEWER, admin, Roles::VIEWER]),
+            Some(0)
+        );
+        assert_eq!(
+            evicted_bond([admin, Roles::ADMIN, Roles::HUB, Roles::VIEWER]),
+            Some(2)
+        );
+        assert_eq!(evicted_bond([admin, Roles::ADMIN, admin, admin]), None);
+    }
+}
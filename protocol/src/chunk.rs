@@ -0,0 +1,105 @@
+//! Encoding of frames sent to the dashboard.
+//!
+//! The frame is sent in chunks of 128 pixels, row by row from the top left corner as
+//! seen by the hub, chunks wrapping around to the next row. Each group of 8 pixels is
+//! encoded in two bytes, the first holding black (0) or not, the second holding white (1)
+//! or chromatic (0) for pixels that aren't black, least significant bit first.
+
+/// Number of pixels in one chunk
+pub const CHUNK_PIXELS: usize = 128;
+/// Number of bytes encoding one chunk of 128 pixels
+pub const CHUNK_LEN: usize = CHUNK_PIXELS / 4;
+/// Number of pixels of the panel
+pub const PIXEL_COUNT: u32 = 800 * 480;
+/// Number of chunks needed to fill the whole display
+pub const CHUNK_COUNT: u32 = PIXEL_COUNT / CHUNK_PIXELS as u32;
+
+/// Colors shown by the tri-color panel
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Color {
+    Black,
+    White,
+    /// Red or yellow, depending on the panel
+    Chromatic,
+}
+
+/// Decode the 128 pixels of a chunk.
+pub fn decode(bytes: &[u8; CHUNK_LEN]) -> [Color; CHUNK_PIXELS] {
+    let mut result = [Color::White; CHUNK_PIXELS];
+    for i in 0usize..16 {
+        let k = i * 2;
+
+        for j in 0u8..8 {
+            let black = bytes[k] >> j & 1;
+            let color = bytes[k + 1] >> j & 1;
+
+            if black == 1 && color == 1 {
+            } else {
+                result[i * 8 + (j as usize)] = if black == 0 {
+                    Color::Black
+                } else {
+                    Color::Chromatic
+                };
+            }
+        }
+    }
+
+    result
+}
+
+/// Encode 128 pixels into a chunk, black pixels have both bits cleared.
+pub fn encode(colors: &[Color; CHUNK_PIXELS]) -> [u8; CHUNK_LEN] {
+    let mut result = [0u8; CHUNK_LEN];
+    for (i, group) in colors.chunks_exact(8).enumerate() {
+        for (j, color) in group.iter().enumerate() {
+            let (black, color) = match color {
+                Color::Black => (0, 0),
+                Color::White => (1, 1),
+                Color::Chromatic => (1, 0),
+            };
+            result[i * 2] |= black << j;
+            result[i * 2 + 1] |= color << j;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bits_select_colors() {
+        let mut bytes = [0xFF; CHUNK_LEN];
+        // pixels 0 and 1 are black, whatever the second byte says
+        bytes[0] = 0b1111_1100;
+        bytes[1] = 0b1111_1110;
+        // pixel 10 is chromatic
+        bytes[3] = 0b1111_1011;
+        let colors = decode(&bytes);
+        assert_eq!(colors[0], Color::Black);
+        assert_eq!(colors[1], Color::Black);
+        assert_eq!(colors[2], Color::White);
+        assert_eq!(colors[10], Color::Chromatic);
+        assert_eq!(colors[127], Color::White);
+    }
+
+    #[test]
+    fn round_trip() {
+        let colors: [Color; CHUNK_PIXELS] = core::array::from_fn(|i| match i % 3 {
+            0 => Color::Black,
+            1 => Color::White,
+            _ => Color::Chromatic,
+        });
+        assert_eq!(decode(&encode(&colors)), colors);
+    }
+
+    #[test]
+    fn uniform_chunks() {
+        assert_eq!(encode(&[Color::Black; CHUNK_PIXELS]), [0x00; CHUNK_LEN]);
+        assert_eq!(encode(&[Color::White; CHUNK_PIXELS]), [0xFF; CHUNK_LEN]);
+        let chromatic = encode(&[Color::Chromatic; CHUNK_PIXELS]);
+        assert!(chromatic.chunks(2).all(|pair| pair == [0xFF, 0x00]));
+    }
+}
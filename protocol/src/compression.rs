@@ -0,0 +1,203 @@
+//! PackBits run-length encoding of the encoded frame, which shrinks the black and white
+//! areas of dashboards considerably. Chromatic areas alternate between two bytes and
+//! stay about the same size.
+//!
+//! The data is a sequence of packets, each starting with a header byte `n`:
+//! - `0..=127`: the following `n + 1` bytes are copied as they are
+//! - `129..=255`: the following byte is repeated `257 - n` times
+//! - `128`: no data, skipped
+
+/// Longest run or literal sequence of one packet
+const MAX_PACKET: usize = 128;
+
+/// Compress `data`, passing the compressed data to `out` piece by piece.
+pub fn compress(data: &[u8], mut out: impl FnMut(&[u8])) {
+    let mut rest = data;
+    while !rest.is_empty() {
+        let run = run_len(rest);
+        if run >= 2 {
+            out(&[(257 - run) as u8, rest[0]]);
+            rest = &rest[run..];
+            continue;
+        }
+        // literals end where a run of at least 3 bytes starts, shorter runs don't pay off
+        let mut len = 1;
+        while len < rest.len().min(MAX_PACKET) && run_len(&rest[len..]) < 3 {
+            len += 1;
+        }
+        out(&[(len - 1) as u8]);
+        out(&rest[..len]);
+        rest = &rest[len..];
+    }
+}
+
+/// Length of the run of equal bytes `data` starts with, limited to one packet.
+fn run_len(data: &[u8]) -> usize {
+    match data.first() {
+        Some(&first) => data
+            .iter()
+            .take(MAX_PACKET)
+            .take_while(|&&byte| byte == first)
+            .count(),
+        None => 0,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum State {
+    /// Waiting for the header of the next packet
+    Header,
+    /// Copying the remaining bytes of a literal packet
+    Literal(usize),
+    /// Waiting for the byte to repeat
+    Run(usize),
+}
+
+/// Decompresses data arriving in pieces, e.g. SDUs that don't align with packets.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Decompressor {
+    state: State,
+}
+
+impl Default for Decompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decompressor {
+    pub const fn new() -> Self {
+        Decompressor {
+            state: State::Header,
+        }
+    }
+
+    /// Decompress the next piece of compressed data, passing the decompressed data to
+    /// `out` piece by piece and stopping at its first error.
+    pub fn feed<E>(
+        &mut self,
+        mut data: &[u8],
+        mut out: impl FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        while let Some((&first, rest)) = data.split_first() {
+            match self.state {
+                State::Header => {
+                    self.state = match first {
+                        0..=127 => State::Literal(first as usize + 1),
+                        128 => State::Header,
+                        _ => State::Run(257 - first as usize),
+                    };
+                    data = rest;
+                }
+                State::Literal(remaining) => {
+                    let len = remaining.min(data.len());
+                    out(&data[..len])?;
+                    self.state = match remaining - len {
+                        0 => State::Header,
+                        remaining => State::Literal(remaining),
+                    };
+                    data = &data[len..];
+                }
+                State::Run(len) => {
+                    out(&[first; MAX_PACKET][..len])?;
+                    self.state = State::Header;
+                    data = rest;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the data fed so far ends with a complete packet.
+    pub fn is_idle(&self) -> bool {
+        self.state == State::Header
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use core::convert::Infallible;
+    use std::vec::Vec;
+
+    use super::*;
+
+    fn compressed(data: &[u8]) -> Vec<u8> {
+        let mut result = Vec::new();
+        compress(data, |piece| result.extend_from_slice(piece));
+        result
+    }
+
+    /// Decompress `data` fed in pieces of `piece_len` bytes.
+    fn decompressed(data: &[u8], piece_len: usize) -> Vec<u8> {
+        let mut decompressor = Decompressor::new();
+        let mut result = Vec::new();
+        for piece in data.chunks(piece_len) {
+            let Ok(()) = decompressor.feed::<Infallible>(piece, |piece| {
+                result.extend_from_slice(piece);
+                Ok(())
+            });
+        }
+        assert!(decompressor.is_idle());
+        result
+    }
+
+    #[test]
+    fn packets() {
+        assert_eq!(compressed(&[7; 5]), [252, 7]);
+        assert_eq!(compressed(&[1, 2, 3]), [2, 1, 2, 3]);
+        assert_eq!(compressed(&[1, 2, 5, 5, 5, 5]), [1, 1, 2, 253, 5]);
+        assert_eq!(compressed(&[]), []);
+    }
+
+    #[test]
+    fn long_runs_are_split() {
+        let data = [0xFF; 300];
+        let result = compressed(&data);
+        assert_eq!(result, [129, 0xFF, 129, 0xFF, 213, 0xFF]);
+        assert_eq!(decompressed(&result, 1), data);
+    }
+
+    #[test]
+    fn round_trip_in_pieces() {
+        let data: Vec<u8> = (0..4000u32)
+            .map(|i| {
+                if i % 1000 < 600 {
+                    0xFF
+                } else {
+                    (i * 7 % 13) as u8
+                }
+            })
+            .collect();
+        let result = compressed(&data);
+        assert!(result.len() < data.len());
+        for piece_len in [1, 2, 3, 100, 1024] {
+            assert_eq!(decompressed(&result, piece_len), data);
+        }
+    }
+
+    #[test]
+    fn no_op_headers_are_skipped() {
+        assert_eq!(decompressed(&[128, 0, 9, 128], 4), [9]);
+    }
+
+    #[test]
+    fn output_errors_stop_decompression() {
+        let mut decompressor = Decompressor::new();
+        let mut calls = 0;
+        let result = decompressor.feed(&[254, 1, 254, 2], |_| {
+            calls += 1;
+            Err("full")
+        });
+        assert_eq!(result, Err("full"));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn truncated_data_is_not_idle() {
+        let mut decompressor = Decompressor::new();
+        let Ok(()) = decompressor.feed::<Infallible>(&[2, 1], |_| Ok(()));
+        assert!(!decompressor.is_idle());
+    }
+}
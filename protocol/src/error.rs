@@ -0,0 +1,11 @@
+/// Errors caused by data received from the other side, e.g. frames or drawing commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// A write targeted a position outside of the frame buffer
+    CursorOutOfRange,
+    /// The data has an invalid length
+    InvalidLength,
+    /// The data holds a value outside of the allowed range
+    InvalidValue,
+}
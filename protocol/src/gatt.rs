@@ -0,0 +1,48 @@
+//! UUIDs of the GATT services and characteristics, all sharing the dashboard's base UUID
+//! `xxxxxxxx-50bf-48a2-9d8a-835aaa2fb179`.
+//!
+//! The Current Time Service uses the standard Bluetooth SIG UUIDs instead.
+
+/// Image transfer, stored images and on-device rendering
+pub mod dashboard {
+    pub const SERVICE: u128 = 0x0001_0000_50bf_48a2_9d8a_835a_aa2f_b179;
+    /// Chunk written at the position of [`CURSOR`]
+    pub const WRITE_BUFFER: u128 = 0x0001_0001_50bf_48a2_9d8a_835a_aa2f_b179;
+    /// Commits the received frame, so it gets displayed
+    pub const WRITE: u128 = 0x0001_0002_50bf_48a2_9d8a_835a_aa2f_b179;
+    /// Index of the chunk the next write to [`WRITE_BUFFER`] goes to
+    pub const CURSOR: u128 = 0x0001_0003_50bf_48a2_9d8a_835a_aa2f_b179;
+    /// [`crate::status::TransferStatus`] of the current transfer
+    pub const STATUS: u128 = 0x0001_0004_50bf_48a2_9d8a_835a_aa2f_b179;
+    /// Chunks written without response, see [`crate::transfer::split_stream`]
+    pub const STREAM: u128 = 0x0001_0005_50bf_48a2_9d8a_835a_aa2f_b179;
+    /// [`crate::transfer::Ack`] of streamed chunks
+    pub const ACK: u128 = 0x0001_0006_50bf_48a2_9d8a_835a_aa2f_b179;
+    /// PSM of the L2CAP image channel
+    pub const L2CAP_PSM: u128 = 0x0001_0007_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const LINK: u128 = 0x0001_0008_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const SLOT_CONTROL: u128 = 0x0001_0009_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const SLOTS: u128 = 0x0001_000a_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const SCHEDULE: u128 = 0x0001_000b_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const TIME: u128 = 0x0001_000c_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const LAYOUT: u128 = 0x0001_000d_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const FONT: u128 = 0x0001_000e_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const REFRESH_STATS: u128 = 0x0001_000f_50bf_48a2_9d8a_835a_aa2f_b179;
+    /// FNV-1a hash of the image shown, see [`crate::hash::fnv1a`]
+    pub const IMAGE_HASH: u128 = 0x0001_0010_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const TEMPERATURE: u128 = 0x0001_0011_50bf_48a2_9d8a_835a_aa2f_b179;
+}
+
+/// Device configuration
+pub mod settings {
+    pub const SERVICE: u128 = 0x0002_0000_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const STATUS: u128 = 0x0002_0001_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const ADVERTISING: u128 = 0x0002_0002_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const NAME: u128 = 0x0002_0003_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const LABEL: u128 = 0x0002_0004_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const ROLES: u128 = 0x0002_0005_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const SLIDESHOW: u128 = 0x0002_0006_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const WIDGET: u128 = 0x0002_0007_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const ORIENTATION: u128 = 0x0002_0008_50bf_48a2_9d8a_835a_aa2f_b179;
+    pub const REFRESH_POLICY: u128 = 0x0002_0009_50bf_48a2_9d8a_835a_aa2f_b179;
+}
@@ -0,0 +1,11 @@
+/// 32 bit FNV-1a hash, cheap enough to run over a whole frame buffer.
+pub fn fnv1a(data: &[u8]) -> u32 {
+    fnv1a_update(0x811c_9dc5, data)
+}
+
+/// Continue an FNV-1a `hash` with `data`, for data that isn't contiguous.
+pub fn fnv1a_update(hash: u32, data: &[u8]) -> u32 {
+    data.iter().fold(hash, |hash, &byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
@@ -0,0 +1,18 @@
+//! Bluetooth LE protocol between the dashboard and its hub: GATT UUIDs, the messages
+//! exchanged during image transfers and the encoding of the transferred frames.
+//!
+//! Shared by the firmware and hubs, so both sides agree on the format.
+#![no_std]
+
+pub mod access;
+pub mod chunk;
+pub mod compression;
+mod error;
+pub mod gatt;
+pub mod hash;
+pub mod schedule;
+pub mod status;
+pub mod time;
+pub mod transfer;
+
+pub use error::Error;
@@ -0,0 +1,263 @@
+use crate::Error;
+
+pub const MAX_ENTRIES: usize = 16;
+/// Encoded as `[days, start (u16, minutes), end (u16, minutes), slot]`, little endian
+const ENTRY_LEN: usize = 6;
+/// Encoded as `[entry count, entries]`
+pub const SCHEDULE_LEN: usize = 1 + MAX_ENTRIES * ENTRY_LEN;
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// Show a stored image during a time range on the selected days.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Entry {
+    /// Bit `n` selects the `n`th day of the week, starting with Monday
+    pub days: u8,
+    /// Minutes since midnight in local time, a range ending before its start
+    /// continues on the next day
+    pub start: u16,
+    pub end: u16,
+    pub slot: u8,
+}
+
+impl Entry {
+    const UNUSED: Entry = Entry {
+        days: 0,
+        start: 0,
+        end: 0,
+        slot: 0,
+    };
+
+    fn encode(&self, buf: &mut [u8]) {
+        buf[0] = self.days;
+        buf[1..3].copy_from_slice(&self.start.to_le_bytes());
+        buf[3..5].copy_from_slice(&self.end.to_le_bytes());
+        buf[5] = self.slot;
+    }
+
+    fn decode(buf: &[u8], slot_count: usize) -> Result<Self, Error> {
+        let entry = Entry {
+            days: buf[0],
+            start: u16::from_le_bytes([buf[1], buf[2]]),
+            end: u16::from_le_bytes([buf[3], buf[4]]),
+            slot: buf[5],
+        };
+        if entry.days & 0x80 != 0
+            || entry.start >= MINUTES_PER_DAY
+            || entry.end >= MINUTES_PER_DAY
+            || entry.slot as usize >= slot_count
+        {
+            return Err(Error::InvalidValue);
+        }
+        Ok(entry)
+    }
+
+    /// Whether the entry applies on `weekday` (0 for Monday) at `minutes` since midnight.
+    pub fn is_active(&self, weekday: u8, minutes: u16) -> bool {
+        let runs_on = |weekday: u8| self.days & (1 << weekday) != 0;
+        if self.start <= self.end {
+            runs_on(weekday) && (self.start..self.end).contains(&minutes)
+        } else if minutes >= self.start {
+            runs_on(weekday)
+        } else {
+            // continued from the previous day
+            minutes < self.end && runs_on((weekday + 6) % 7)
+        }
+    }
+}
+
+/// Images shown by the device at set times, written by the hub to the schedule
+/// characteristic.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Schedule {
+    entries: [Entry; MAX_ENTRIES],
+    len: usize,
+}
+
+impl Schedule {
+    pub const fn new() -> Self {
+        Schedule {
+            entries: [Entry::UNUSED; MAX_ENTRIES],
+            len: 0,
+        }
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries[..self.len]
+    }
+
+    /// Slot to be shown on `weekday` at `minutes` since midnight, the first matching entry
+    /// wins.
+    pub fn active_slot(&self, weekday: u8, minutes: u16) -> Option<u8> {
+        self.entries()
+            .iter()
+            .find(|entry| entry.is_active(weekday, minutes))
+            .map(|entry| entry.slot)
+    }
+
+    pub fn encode(&self) -> [u8; SCHEDULE_LEN] {
+        let mut encoded = [0u8; SCHEDULE_LEN];
+        encoded[0] = self.len as u8;
+        for (entry, buf) in self
+            .entries()
+            .iter()
+            .zip(encoded[1..].chunks_exact_mut(ENTRY_LEN))
+        {
+            entry.encode(buf);
+        }
+        encoded
+    }
+
+    /// Decode a schedule whose entries show one of the first `slot_count` slots.
+    pub fn decode(data: &[u8], slot_count: usize) -> Result<Self, Error> {
+        let data: &[u8; SCHEDULE_LEN] = data.try_into().map_err(|_| Error::InvalidLength)?;
+        let len = data[0] as usize;
+        if len > MAX_ENTRIES {
+            return Err(Error::InvalidValue);
+        }
+        let mut schedule = Schedule::new();
+        for (entry, buf) in schedule.entries[..len]
+            .iter_mut()
+            .zip(data[1..].chunks_exact(ENTRY_LEN))
+        {
+            *entry = Entry::decode(buf, slot_count)?;
+        }
+        schedule.len = len;
+        Ok(schedule)
+    }
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEEKDAYS: u8 = 0b0001_1111;
+
+    fn schedule(entries: &[Entry]) -> [u8; SCHEDULE_LEN] {
+        let mut encoded = [0u8; SCHEDULE_LEN];
+        encoded[0] = entries.len() as u8;
+        for (entry, buf) in entries.iter().zip(encoded[1..].chunks_exact_mut(ENTRY_LEN)) {
+            entry.encode(buf);
+        }
+        encoded
+    }
+
+    #[test]
+    fn round_trip() {
+        let entries = [
+            Entry {
+                days: WEEKDAYS,
+                start: 8 * 60,
+                end: 17 * 60,
+                slot: 1,
+            },
+            Entry {
+                days: 0b0110_0000,
+                start: 22 * 60,
+                end: 6 * 60,
+                slot: 7,
+            },
+        ];
+        let encoded = schedule(&entries);
+        assert_eq!(&encoded[..7], &[2, WEEKDAYS, 0xE0, 0x01, 0xFC, 0x03, 1]);
+        let decoded = Schedule::decode(&encoded, 8).unwrap();
+        assert_eq!(decoded.entries(), &entries);
+        assert_eq!(decoded.encode(), encoded);
+    }
+
+    #[test]
+    fn invalid_schedules_are_rejected() {
+        let entry = Entry {
+            days: WEEKDAYS,
+            start: 0,
+            end: 60,
+            slot: 0,
+        };
+        assert_eq!(Schedule::decode(&[0; 7], 8), Err(Error::InvalidLength));
+        let mut too_many = schedule(&[]);
+        too_many[0] = MAX_ENTRIES as u8 + 1;
+        assert_eq!(Schedule::decode(&too_many, 8), Err(Error::InvalidValue));
+        for invalid in [
+            Entry {
+                days: 0x80,
+                ..entry
+            },
+            Entry {
+                start: MINUTES_PER_DAY,
+                ..entry
+            },
+            Entry {
+                end: MINUTES_PER_DAY,
+                ..entry
+            },
+            Entry { slot: 8, ..entry },
+        ] {
+            assert_eq!(
+                Schedule::decode(&schedule(&[entry, invalid]), 8),
+                Err(Error::InvalidValue)
+            );
+        }
+    }
+
+    #[test]
+    fn entries_apply_on_their_days() {
+        let entry = Entry {
+            days: WEEKDAYS,
+            start: 8 * 60,
+            end: 17 * 60,
+            slot: 0,
+        };
+        assert!(entry.is_active(0, 8 * 60));
+        assert!(entry.is_active(4, 17 * 60 - 1));
+        assert!(!entry.is_active(4, 17 * 60));
+        assert!(!entry.is_active(0, 8 * 60 - 1));
+        assert!(!entry.is_active(5, 12 * 60));
+    }
+
+    #[test]
+    fn ranges_continue_past_midnight() {
+        // Friday night only
+        let entry = Entry {
+            days: 1 << 4,
+            start: 22 * 60,
+            end: 6 * 60,
+            slot: 0,
+        };
+        assert!(entry.is_active(4, 23 * 60));
+        assert!(entry.is_active(5, 5 * 60));
+        assert!(!entry.is_active(5, 6 * 60));
+        assert!(!entry.is_active(4, 5 * 60));
+        // Sunday night continues on Monday
+        let entry = Entry {
+            days: 1 << 6,
+            ..entry
+        };
+        assert!(entry.is_active(0, 60));
+    }
+
+    #[test]
+    fn first_matching_entry_wins() {
+        let all_day = Entry {
+            days: 0x7F,
+            start: 0,
+            end: MINUTES_PER_DAY - 1,
+            slot: 3,
+        };
+        let morning = Entry {
+            days: 0x7F,
+            start: 6 * 60,
+            end: 9 * 60,
+            slot: 5,
+        };
+        let schedule = Schedule::decode(&schedule(&[morning, all_day]), 8).unwrap();
+        assert_eq!(schedule.active_slot(2, 7 * 60), Some(5));
+        assert_eq!(schedule.active_slot(2, 12 * 60), Some(3));
+        assert_eq!(Schedule::new().active_slot(2, 12 * 60), None);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::Error;
+
+/// State of the image transfer, reported to the hub via the status characteristic.
+///
+/// Encoded as `[state, error code, received bytes (u32, little endian)]`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TransferStatus {
+    Idle,
+    Receiving {
+        bytes: u32,
+    },
+    Decoding,
+    Refreshing,
+    Done,
+    Error {
+        code: u8,
+    },
+    /// Done, but the display already showed the frame, so it wasn't refreshed
+    Unchanged,
+}
+
+impl TransferStatus {
+    pub const ENCODED_LEN: usize = 6;
+
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let (state, code, bytes) = match *self {
+            TransferStatus::Idle => (0, 0, 0),
+            TransferStatus::Receiving { bytes } => (1, 0, bytes),
+            TransferStatus::Decoding => (2, 0, 0),
+            TransferStatus::Refreshing => (3, 0, 0),
+            TransferStatus::Done => (4, 0, 0),
+            TransferStatus::Error { code } => (5, code, 0),
+            TransferStatus::Unchanged => (6, 0, 0),
+        };
+        let mut encoded = [0u8; Self::ENCODED_LEN];
+        encoded[0] = state;
+        encoded[1] = code;
+        encoded[2..].copy_from_slice(&u32::to_le_bytes(bytes));
+        encoded
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let data: &[u8; Self::ENCODED_LEN] = data.try_into().map_err(|_| Error::InvalidLength)?;
+        let bytes = u32::from_le_bytes([data[2], data[3], data[4], data[5]]);
+        Ok(match data[0] {
+            0 => TransferStatus::Idle,
+            1 => TransferStatus::Receiving { bytes },
+            2 => TransferStatus::Decoding,
+            3 => TransferStatus::Refreshing,
+            4 => TransferStatus::Done,
+            5 => TransferStatus::Error { code: data[1] },
+            6 => TransferStatus::Unchanged,
+            _ => return Err(Error::InvalidValue),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for status in [
+            TransferStatus::Idle,
+            TransferStatus::Receiving { bytes: 0x0102_0304 },
+            TransferStatus::Decoding,
+            TransferStatus::Refreshing,
+            TransferStatus::Done,
+            TransferStatus::Error { code: 7 },
+            TransferStatus::Unchanged,
+        ] {
+            assert_eq!(TransferStatus::decode(&status.encode()), Ok(status));
+        }
+    }
+
+    #[test]
+    fn receiving_is_little_endian() {
+        let encoded = TransferStatus::Receiving { bytes: 0x0102_0304 }.encode();
+        assert_eq!(encoded, [1, 0, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn invalid_status() {
+        assert_eq!(TransferStatus::decode(&[1, 0]), Err(Error::InvalidLength));
+        assert_eq!(
+            TransferStatus::decode(&[9, 0, 0, 0, 0, 0]),
+            Err(Error::InvalidValue)
+        );
+    }
+}
@@ -0,0 +1,372 @@
+use core::ops::RangeInclusive;
+
+use crate::Error;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+/// Time zones range from UTC-12:00 to UTC+14:00
+pub const UTC_OFFSET_RANGE: RangeInclusive<i16> = -12 * 60..=14 * 60;
+
+/// Local date and time.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LocalTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    /// Day of the week, starting with 0 for Monday
+    pub weekday: u8,
+    /// Minutes since midnight
+    pub minutes: u16,
+    pub seconds: u8,
+}
+
+impl LocalTime {
+    /// Local time `unix` seconds after the Unix epoch, in the time zone `utc_offset`
+    /// minutes ahead of UTC.
+    pub fn from_unix(unix: u64, utc_offset: i16) -> Option<Self> {
+        let local = unix.checked_add_signed(i64::from(utc_offset) * 60)?;
+        let days = local / SECONDS_PER_DAY;
+        let seconds = local % SECONDS_PER_DAY;
+        let (year, month, day) = civil_from_days(days);
+        Some(LocalTime {
+            year,
+            month,
+            day,
+            weekday: weekday(days),
+            minutes: (seconds / 60) as u16,
+            seconds: (seconds % 60) as u8,
+        })
+    }
+
+    /// Seconds since the Unix epoch, in the time zone `utc_offset` minutes ahead of UTC.
+    pub fn to_unix(&self, utc_offset: i16) -> Option<u64> {
+        let local = days_from_civil(self.year, self.month, self.day) * SECONDS_PER_DAY
+            + u64::from(self.minutes) * 60
+            + u64::from(self.seconds);
+        local.checked_add_signed(-i64::from(utc_offset) * 60)
+    }
+}
+
+/// Time written by the hub to the time characteristic.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimeSync {
+    /// Seconds since the Unix epoch
+    pub unix: u32,
+    /// Offset of the local time zone to UTC in minutes
+    pub utc_offset: i16,
+}
+
+impl TimeSync {
+    /// Encoded as `[seconds since the Unix epoch (u32), UTC offset (i16, minutes)]`, little
+    /// endian.
+    pub const ENCODED_LEN: usize = 6;
+
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut encoded = [0u8; Self::ENCODED_LEN];
+        encoded[0..4].copy_from_slice(&self.unix.to_le_bytes());
+        encoded[4..6].copy_from_slice(&self.utc_offset.to_le_bytes());
+        encoded
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let data: &[u8; Self::ENCODED_LEN] = data.try_into().map_err(|_| Error::InvalidLength)?;
+        let unix = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        let utc_offset = i16::from_le_bytes([data[4], data[5]]);
+        if !UTC_OFFSET_RANGE.contains(&utc_offset) {
+            return Err(Error::InvalidValue);
+        }
+        Ok(TimeSync { unix, utc_offset })
+    }
+}
+
+/// Value of the Current Time characteristic of the Current Time Service.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CurrentTime {
+    pub time: LocalTime,
+    /// Fraction of the second in 1/256 seconds
+    pub fraction: u8,
+    /// Combination of the `ADJUST_*` flags, why the time was last changed
+    pub adjust_reason: u8,
+}
+
+impl CurrentTime {
+    /// Encoded as `[year (u16), month, day, hours, minutes, seconds, day of week,
+    /// 1/256 seconds, adjust reason]` in local time, little endian.
+    pub const ENCODED_LEN: usize = 10;
+    /// The time was set through the Current Time characteristic
+    pub const ADJUST_MANUAL: u8 = 1 << 0;
+    /// The time was synced from an external reference, e.g. by the hub
+    pub const ADJUST_EXTERNAL_REFERENCE: u8 = 1 << 1;
+    /// The time zone or the DST offset changed
+    pub const ADJUST_TIME_ZONE: u8 = 1 << 2;
+
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let time = &self.time;
+        let mut encoded = [0u8; Self::ENCODED_LEN];
+        encoded[0..2].copy_from_slice(&time.year.to_le_bytes());
+        encoded[2] = time.month;
+        encoded[3] = time.day;
+        encoded[4] = (time.minutes / 60) as u8;
+        encoded[5] = (time.minutes % 60) as u8;
+        encoded[6] = time.seconds;
+        // the Current Time Service counts days from 1 for Monday
+        encoded[7] = time.weekday + 1;
+        encoded[8] = self.fraction;
+        encoded[9] = self.adjust_reason;
+        encoded
+    }
+
+    /// Decode a written value, the day of the week is derived from the date instead.
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let data: &[u8; Self::ENCODED_LEN] = data.try_into().map_err(|_| Error::InvalidLength)?;
+        let year = u16::from_le_bytes([data[0], data[1]]);
+        let (month, day, hours, minutes, seconds) = (data[2], data[3], data[4], data[5], data[6]);
+        if !(1970..=9999).contains(&year)
+            || !(1..=12).contains(&month)
+            || !(1..=days_in_month(year, month)).contains(&day)
+            || hours > 23
+            || minutes > 59
+            || seconds > 59
+        {
+            return Err(Error::InvalidValue);
+        }
+        Ok(CurrentTime {
+            time: LocalTime {
+                year,
+                month,
+                day,
+                weekday: weekday(days_from_civil(year, month, day)),
+                minutes: u16::from(hours) * 60 + u16::from(minutes),
+                seconds,
+            },
+            fraction: data[8],
+            adjust_reason: data[9],
+        })
+    }
+}
+
+/// Value of the Local Time Information characteristic of the Current Time Service.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LocalTimeInformation {
+    /// Offset of the local time zone to UTC in minutes, including DST
+    pub utc_offset: i16,
+}
+
+impl LocalTimeInformation {
+    /// Encoded as `[time zone (i8, 15 minutes), DST offset (15 minutes)]`.
+    pub const ENCODED_LEN: usize = 2;
+
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        // DST is already included in the offset
+        [(self.utc_offset / 15) as i8 as u8, 0]
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let data: &[u8; Self::ENCODED_LEN] = data.try_into().map_err(|_| Error::InvalidLength)?;
+        let time_zone = data[0] as i8;
+        // 255 marks an unknown DST offset, the other valid values are multiples of 30 minutes
+        let dst_offset = match data[1] {
+            255 => 0,
+            offset @ (0 | 2 | 4 | 8) => offset,
+            _ => return Err(Error::InvalidValue),
+        };
+        let utc_offset = (i16::from(time_zone) + i16::from(dst_offset)) * 15;
+        if time_zone == i8::MIN || !UTC_OFFSET_RANGE.contains(&utc_offset) {
+            return Err(Error::InvalidValue);
+        }
+        Ok(LocalTimeInformation { utc_offset })
+    }
+}
+
+fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Day of the week of a day since the Unix epoch, starting with 0 for Monday.
+fn weekday(days: u64) -> u8 {
+    // the epoch was a Thursday
+    ((days + 3) % 7) as u8
+}
+
+/// Days since the Unix epoch of a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: u16, month: u8, day: u8) -> u64 {
+    let year = u64::from(year) - u64::from(month <= 2);
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    // months counted from March, so the leap day is at the end of the year
+    let month = u64::from(month);
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * shifted_month + 2) / 5 + u64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Date in the proleptic Gregorian calendar of a day since the Unix epoch.
+fn civil_from_days(days: u64) -> (u16, u8, u8) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u8;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u8;
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year as u16, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2026-10-19 14:05:30 UTC, a Monday
+    const UNIX: u64 = 1_792_418_730;
+
+    fn current_time(year: u16, month: u8, day: u8) -> [u8; CurrentTime::ENCODED_LEN] {
+        let [low, high] = year.to_le_bytes();
+        [low, high, month, day, 12, 30, 0, 0, 0x80, 0]
+    }
+
+    #[test]
+    fn unix_time_to_local_time() {
+        let time = LocalTime::from_unix(UNIX, 120).unwrap();
+        assert_eq!(
+            time,
+            LocalTime {
+                year: 2026,
+                month: 10,
+                day: 19,
+                weekday: 0,
+                minutes: 16 * 60 + 5,
+                seconds: 30,
+            }
+        );
+        assert_eq!(time.to_unix(120), Some(UNIX));
+        // the local date differs from the UTC date
+        let time = LocalTime::from_unix(UNIX, 14 * 60).unwrap();
+        assert_eq!((time.day, time.weekday), (20, 1));
+        assert_eq!(LocalTime::from_unix(0, -60), None);
+    }
+
+    #[test]
+    fn dates_round_trip() {
+        for days in [0, 59, 365, 10_956, 11_016, 20_745, 2_932_896] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+    }
+
+    #[test]
+    fn time_sync_round_trip() {
+        let sync = TimeSync {
+            unix: 0x0102_0304,
+            utc_offset: -300,
+        };
+        assert_eq!(sync.encode(), [4, 3, 2, 1, 0xD4, 0xFE]);
+        assert_eq!(TimeSync::decode(&sync.encode()), Ok(sync));
+        let sync = TimeSync {
+            utc_offset: 15 * 60,
+            ..sync
+        };
+        assert_eq!(TimeSync::decode(&sync.encode()), Err(Error::InvalidValue));
+        assert_eq!(TimeSync::decode(&[0; 4]), Err(Error::InvalidLength));
+    }
+
+    #[test]
+    fn current_time_round_trip() {
+        let time = CurrentTime {
+            time: LocalTime::from_unix(UNIX, 0).unwrap(),
+            fraction: 0x80,
+            adjust_reason: CurrentTime::ADJUST_EXTERNAL_REFERENCE,
+        };
+        let encoded = time.encode();
+        assert_eq!(encoded, [0xEA, 0x07, 10, 19, 14, 5, 30, 1, 0x80, 2]);
+        assert_eq!(CurrentTime::decode(&encoded), Ok(time));
+        assert_eq!(
+            CurrentTime::decode(&encoded[..9]),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn current_time_rejects_days_past_the_end_of_the_month() {
+        for (year, month, last_day) in [
+            (2026, 1, 31),
+            (2026, 2, 28),
+            (2028, 2, 29),
+            (2000, 2, 29),
+            (2100, 2, 28),
+            (2026, 4, 30),
+            (2026, 6, 30),
+            (2026, 9, 30),
+            (2026, 11, 30),
+            (2026, 12, 31),
+        ] {
+            let time = CurrentTime::decode(&current_time(year, month, last_day)).unwrap();
+            assert_eq!((time.time.month, time.time.day), (month, last_day));
+            assert_eq!(
+                CurrentTime::decode(&current_time(year, month, last_day + 1)),
+                Err(Error::InvalidValue)
+            );
+        }
+        assert_eq!(
+            CurrentTime::decode(&current_time(2026, 13, 1)),
+            Err(Error::InvalidValue)
+        );
+        assert_eq!(
+            CurrentTime::decode(&current_time(2026, 1, 0)),
+            Err(Error::InvalidValue)
+        );
+    }
+
+    #[test]
+    fn current_time_ignores_the_written_weekday() {
+        let mut encoded = current_time(2026, 10, 19);
+        encoded[7] = 5;
+        let time = CurrentTime::decode(&encoded).unwrap();
+        assert_eq!(time.time.weekday, 0);
+        assert_eq!(time.time.minutes, 12 * 60 + 30);
+    }
+
+    #[test]
+    fn local_time_information() {
+        // UTC+01:00 with one hour DST
+        let info = LocalTimeInformation::decode(&[4, 4]).unwrap();
+        assert_eq!(info.utc_offset, 120);
+        assert_eq!(info.encode(), [8, 0]);
+        assert_eq!(
+            LocalTimeInformation::decode(&[(-20i8) as u8, 255]),
+            Ok(LocalTimeInformation { utc_offset: -300 })
+        );
+        assert_eq!(
+            LocalTimeInformation::decode(&[0, 3]),
+            Err(Error::InvalidValue)
+        );
+        assert_eq!(
+            LocalTimeInformation::decode(&[0x80, 0]),
+            Err(Error::InvalidValue)
+        );
+        assert_eq!(
+            LocalTimeInformation::decode(&[57, 0]),
+            Err(Error::InvalidValue)
+        );
+    }
+}
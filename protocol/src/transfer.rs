@@ -0,0 +1,247 @@
+//! Framing of image transfers.
+//!
+//! The encoded frame (see [`crate::chunk`]) can be sent in three ways:
+//! - chunk by chunk, setting the cursor with each write to `write_buffer` and committing
+//!   the frame by writing to `write`
+//! - streamed without response, each write to `stream` starting with the index of its
+//!   first chunk, acknowledged every [`ACK_INTERVAL`] writes
+//! - over an L2CAP channel, from the first chunk onwards in SDUs of any size, optionally
+//!   compressed with [`crate::compression`]
+//!
+//! On the L2CAP channel, data following the last chunk of the frame in the same SDU is a
+//! protocol error. The frame is complete at that point, so it is still shown before the
+//! error is reported.
+
+use crate::Error;
+use crate::chunk::{CHUNK_COUNT, CHUNK_LEN};
+
+/// Largest value accepted by the write characteristics (ATT MTU of 247 minus the ATT header)
+pub const MAX_WRITE_LEN: usize = 244;
+/// Streamed writes are prefixed with the index of their first chunk
+pub const STREAM_HEADER_LEN: usize = 4;
+/// Number of whole chunks fitting into one streamed write
+pub const STREAM_CHUNKS: usize = (MAX_WRITE_LEN - STREAM_HEADER_LEN) / CHUNK_LEN;
+/// Number of streamed writes after which an acknowledgement is notified
+pub const ACK_INTERVAL: u32 = 8;
+
+/// Protocol/Service Multiplexer of the image channel, from the dynamic LE range
+pub const L2CAP_PSM: u16 = 0x0081;
+/// Image channel receiving the frame compressed with [`crate::compression`]
+pub const L2CAP_COMPRESSED_PSM: u16 = 0x0082;
+/// Largest SDU accepted on the image channels
+pub const L2CAP_MTU: u16 = 1024;
+
+/// Acknowledgement of streamed chunks.
+///
+/// Encoded as `[first missing chunk (u32), bitmap of the following 32 chunks (u32)]`,
+/// little endian.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ack {
+    /// Index of the first chunk that has not been received yet
+    pub first_missing: u32,
+    /// Bit n is set if chunk `first_missing + n` has been received
+    pub received: u32,
+}
+
+impl Ack {
+    pub const ENCODED_LEN: usize = 8;
+
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut encoded = [0u8; Self::ENCODED_LEN];
+        encoded[..4].copy_from_slice(&self.first_missing.to_le_bytes());
+        encoded[4..].copy_from_slice(&self.received.to_le_bytes());
+        encoded
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let data: &[u8; Self::ENCODED_LEN] = data.try_into().map_err(|_| Error::InvalidLength)?;
+        Ok(Ack {
+            first_missing: u32::from_le_bytes([data[0], data[1], data[2], data[3]]),
+            received: u32::from_le_bytes([data[4], data[5], data[6], data[7]]),
+        })
+    }
+
+    /// Whether the chunk at `index` is known to be received.
+    pub fn is_received(&self, index: u32) -> bool {
+        match index.checked_sub(self.first_missing) {
+            Some(offset) => offset < 32 && self.received & 1 << offset != 0,
+            None => true,
+        }
+    }
+}
+
+/// Prefix of a streamed write starting with the chunk at `index`.
+pub fn stream_header(index: u32) -> [u8; STREAM_HEADER_LEN] {
+    index.to_le_bytes()
+}
+
+/// Split a streamed write into the index of its first chunk and the chunk data.
+pub fn split_stream(data: &[u8]) -> Result<(u32, &[u8]), Error> {
+    if data.len() < STREAM_HEADER_LEN {
+        return Err(Error::InvalidLength);
+    }
+    let (header, chunks) = data.split_at(STREAM_HEADER_LEN);
+    let index = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    Ok((index, chunks))
+}
+
+/// Reassembles the chunks of a frame streamed over the L2CAP channel, whose SDUs don't
+/// have to be aligned to chunk boundaries.
+pub struct ChunkAssembler {
+    next: u32,
+    pending: [u8; CHUNK_LEN],
+    pending_len: usize,
+    excess: usize,
+}
+
+impl ChunkAssembler {
+    pub const fn new() -> Self {
+        ChunkAssembler {
+            next: 0,
+            pending: [0; CHUNK_LEN],
+            pending_len: 0,
+            excess: 0,
+        }
+    }
+
+    /// Append received data, calling `chunk` with the index and content of every chunk it
+    /// completes. Data following the last chunk of the frame is only counted, see
+    /// [`ChunkAssembler::excess`].
+    pub fn push<E>(
+        &mut self,
+        mut data: &[u8],
+        mut chunk: impl FnMut(u32, &[u8; CHUNK_LEN]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        while !data.is_empty() {
+            if self.is_complete() {
+                self.excess += data.len();
+                return Ok(());
+            }
+            let take = (CHUNK_LEN - self.pending_len).min(data.len());
+            self.pending[self.pending_len..self.pending_len + take].copy_from_slice(&data[..take]);
+            self.pending_len += take;
+            data = &data[take..];
+
+            if self.pending_len == CHUNK_LEN {
+                chunk(self.next, &self.pending)?;
+                self.next += 1;
+                self.pending_len = 0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether all chunks of the frame have been received.
+    pub fn is_complete(&self) -> bool {
+        self.next == CHUNK_COUNT
+    }
+
+    /// Bytes of the encoded frame received so far.
+    pub fn received_bytes(&self) -> u32 {
+        self.next * CHUNK_LEN as u32 + self.pending_len as u32
+    }
+
+    /// Bytes received after the last chunk of the frame.
+    pub fn excess(&self) -> usize {
+        self.excess
+    }
+}
+
+impl Default for ChunkAssembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ack_round_trip() {
+        let ack = Ack {
+            first_missing: 300,
+            received: 0b1010,
+        };
+        assert_eq!(ack.encode(), [44, 1, 0, 0, 10, 0, 0, 0]);
+        assert_eq!(Ack::decode(&ack.encode()), Ok(ack));
+        assert_eq!(Ack::decode(&[0; 4]), Err(Error::InvalidLength));
+    }
+
+    #[test]
+    fn ack_received_chunks() {
+        let ack = Ack {
+            first_missing: 10,
+            received: 0b10,
+        };
+        assert!(ack.is_received(9));
+        assert!(!ack.is_received(10));
+        assert!(ack.is_received(11));
+        assert!(!ack.is_received(12));
+        assert!(!ack.is_received(42));
+    }
+
+    #[test]
+    fn stream_framing() {
+        let mut write = [0u8; MAX_WRITE_LEN];
+        write[..STREAM_HEADER_LEN].copy_from_slice(&stream_header(0x0102));
+        write[STREAM_HEADER_LEN] = 0xAB;
+        let (index, chunks) = split_stream(&write).unwrap();
+        assert_eq!(index, 0x0102);
+        assert_eq!(chunks.len(), MAX_WRITE_LEN - STREAM_HEADER_LEN);
+        assert_eq!(chunks[0], 0xAB);
+        assert_eq!(split_stream(&[1, 2]), Err(Error::InvalidLength));
+    }
+
+    #[test]
+    fn chunks_are_reassembled_across_sdus() {
+        let mut assembler = ChunkAssembler::new();
+        let mut chunks = 0;
+        let mut push = |assembler: &mut ChunkAssembler, data: &[u8]| {
+            assembler.push::<()>(data, |index, chunk| {
+                assert_eq!(index, chunks);
+                assert_eq!(chunk, &[index as u8; CHUNK_LEN]);
+                chunks += 1;
+                Ok(())
+            })
+        };
+        // SDUs ending in the middle of a chunk
+        let frame: [u8; 3 * CHUNK_LEN] = core::array::from_fn(|i| (i / CHUNK_LEN) as u8);
+        push(&mut assembler, &frame[..CHUNK_LEN + 5]).unwrap();
+        assert_eq!(assembler.received_bytes(), CHUNK_LEN as u32 + 5);
+        push(&mut assembler, &frame[CHUNK_LEN + 5..]).unwrap();
+        assert_eq!(assembler.received_bytes(), 3 * CHUNK_LEN as u32);
+        assert!(!assembler.is_complete());
+        assert_eq!(chunks, 3);
+    }
+
+    #[test]
+    fn data_after_the_last_chunk_is_counted() {
+        let mut assembler = ChunkAssembler::new();
+        let mut chunks = 0;
+        let sdu = [0u8; 1000];
+        let frame_len = CHUNK_COUNT as usize * CHUNK_LEN;
+        let mut remaining = frame_len + 10;
+        while remaining > 0 {
+            let len = remaining.min(sdu.len());
+            assembler
+                .push::<()>(&sdu[..len], |_, _| {
+                    chunks += 1;
+                    Ok(())
+                })
+                .unwrap();
+            remaining -= len;
+        }
+        // the frame is still complete, the excess is reported separately
+        assert!(assembler.is_complete());
+        assert_eq!(chunks, CHUNK_COUNT);
+        assert_eq!(assembler.received_bytes(), frame_len as u32);
+        assert_eq!(assembler.excess(), 10);
+    }
+
+    #[test]
+    fn whole_chunks_fit_a_write() {
+        assert_eq!(STREAM_CHUNKS, 7);
+    }
+}
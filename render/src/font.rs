@@ -0,0 +1,138 @@
+use embedded_graphics::prelude::*;
+use epd_waveshare::color::TriColor;
+
+use crate::Error;
+
+/// Marks a complete font, "FONT"
+const MAGIC: u32 = 0x544e_4f46;
+/// Magic (u32), total length (u32), height, letter spacing, glyph count (u16)
+pub const HEADER_LEN: usize = 12;
+/// Encoded as `[code point (u32), width, bitmap offset from the font start (u32)]`
+const GLYPH_LEN: usize = 9;
+
+/// A bitmap font uploaded by the hub, e.g. converted from BDF.
+///
+/// Encoded as `[magic (u32), total length (u32), height, letter spacing, glyph count (u16),
+/// glyphs sorted by code point, bitmaps]`, little endian. Each glyph is
+/// `[code point (u32), width, bitmap offset (u32)]` and its bitmap has `height` rows of
+/// 1 bit per pixel, each padded to whole bytes.
+#[derive(Clone, Copy)]
+pub struct StoredFont<'a> {
+    data: &'a [u8],
+    height: u32,
+    spacing: u32,
+    glyphs: &'a [u8],
+}
+
+impl<'a> StoredFont<'a> {
+    /// The font at the start of `data`, if it is complete and valid.
+    pub fn parse(data: &'a [u8]) -> Result<Self, Error> {
+        let header = data.get(..HEADER_LEN).ok_or(Error::InvalidLength)?;
+        let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let total = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        if magic != MAGIC {
+            return Err(Error::InvalidValue);
+        }
+        if total < HEADER_LEN || total > data.len() {
+            return Err(Error::InvalidLength);
+        }
+        let data = &data[..total];
+        let count = u16::from_le_bytes([data[10], data[11]]) as usize;
+        let font = StoredFont {
+            data,
+            height: data[8].into(),
+            spacing: data[9].into(),
+            glyphs: data
+                .get(HEADER_LEN..HEADER_LEN + count * GLYPH_LEN)
+                .ok_or(Error::InvalidLength)?,
+        };
+        // make sure drawing never reads past the font, offsets near the end of the u32
+        // range must not wrap around
+        let valid = font.glyphs.chunks_exact(GLYPH_LEN).all(|glyph| {
+            let (width, offset) = Self::entry(glyph);
+            let end = width
+                .div_ceil(8)
+                .checked_mul(font.height)
+                .and_then(|len| offset.checked_add(len));
+            end.is_some_and(|end| end as usize <= total)
+        });
+        if !valid {
+            return Err(Error::InvalidValue);
+        }
+        Ok(font)
+    }
+
+    fn entry(glyph: &[u8]) -> (u32, u32) {
+        let offset = u32::from_le_bytes([glyph[5], glyph[6], glyph[7], glyph[8]]);
+        (glyph[4].into(), offset)
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Width and bitmap of the glyph for `c`, falling back to '?' for missing glyphs.
+    fn glyph(&self, c: char) -> Option<(u32, &'a [u8])> {
+        let find = |c: char| {
+            let count = self.glyphs.len() / GLYPH_LEN;
+            let index = binary_search(count, |i| {
+                let glyph = &self.glyphs[i * GLYPH_LEN..];
+                u32::from_le_bytes([glyph[0], glyph[1], glyph[2], glyph[3]]).cmp(&(c as u32))
+            })?;
+            let (width, offset) = Self::entry(&self.glyphs[index * GLYPH_LEN..]);
+            let offset = offset as usize;
+            let len = (width.div_ceil(8) * self.height) as usize;
+            Some((width, &self.data[offset..offset + len]))
+        };
+        find(c).or_else(|| find('?'))
+    }
+
+    /// Width of `c` including the letter spacing.
+    pub fn advance(&self, c: char) -> u32 {
+        self.glyph(c).map_or(0, |(width, _)| width + self.spacing)
+    }
+
+    /// Draw `text` with its top left corner at `position`.
+    pub fn draw<D>(
+        &self,
+        target: &mut D,
+        text: &str,
+        mut position: Point,
+        color: TriColor,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = TriColor>,
+    {
+        for c in text.chars() {
+            let Some((width, bitmap)) = self.glyph(c) else {
+                continue;
+            };
+            let row_len = width.div_ceil(8) as usize;
+            let origin = position;
+            let pixels = (0..self.height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .filter(|&(x, y)| {
+                    bitmap[y as usize * row_len + x as usize / 8] & (0x80 >> (x % 8)) != 0
+                })
+                .map(|(x, y)| Pixel(origin + Point::new(x as i32, y as i32), color));
+            target.draw_iter(pixels)?;
+            position.x += (width + self.spacing) as i32;
+        }
+        Ok(())
+    }
+}
+
+/// Index of the element in a sorted sequence of `len` elements for which `compare`
+/// returns `Equal`.
+fn binary_search(len: usize, compare: impl Fn(usize) -> core::cmp::Ordering) -> Option<usize> {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let mid = (low + high) / 2;
+        match compare(mid) {
+            core::cmp::Ordering::Less => low = mid + 1,
+            core::cmp::Ordering::Greater => high = mid,
+            core::cmp::Ordering::Equal => return Some(mid),
+        }
+    }
+    None
+}
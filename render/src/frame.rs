@@ -0,0 +1,348 @@
+use core::convert::Infallible;
+
+use embedded_graphics::{prelude::*, primitives::Rectangle};
+use epd_waveshare::{
+    color::TriColor,
+    epd7in5b_v2::{Display7in5, HEIGHT, WIDTH},
+};
+
+use periphery_protocol::chunk::{self, Color};
+use periphery_protocol::hash::fnv1a;
+
+use crate::Error;
+
+pub use periphery_protocol::chunk::{CHUNK_COUNT, CHUNK_LEN};
+
+/// Size of the frame buffer, holding a black/white and a chromatic bit plane
+pub const FRAME_LEN: usize = 800 * 480 / 8 * 2;
+/// Size of one bit plane, the black/white plane comes first
+pub const PLANE_LEN: usize = FRAME_LEN / 2;
+/// Bytes per row of a bit plane
+pub const ROW_LEN: usize = WIDTH as usize / 8;
+
+/// Clockwise rotation of the panel as mounted
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Rotation {
+    Rotate0,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+/// How the panel is mounted, applied to everything drawn so the hub can send images
+/// upright, e.g. 480 pixels wide for a panel mounted in portrait.
+///
+/// The frame buffer always holds the panel's native landscape frame, so frames stored in
+/// slots keep the orientation they were received in.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OrientationConfig {
+    pub rotation: Rotation,
+    /// Flip images horizontally, e.g. for panels viewed through a mirror
+    pub mirrored: bool,
+}
+
+impl Default for OrientationConfig {
+    fn default() -> Self {
+        OrientationConfig {
+            rotation: Rotation::Rotate0,
+            mirrored: false,
+        }
+    }
+}
+
+impl OrientationConfig {
+    /// Encoded as `[rotation (0 none, 1 90°, 2 180°, 3 270°), mirrored]`.
+    pub const ENCODED_LEN: usize = 2;
+
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let rotation = match self.rotation {
+            Rotation::Rotate0 => 0,
+            Rotation::Rotate90 => 1,
+            Rotation::Rotate180 => 2,
+            Rotation::Rotate270 => 3,
+        };
+        [rotation, self.mirrored as u8]
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let data: &[u8; Self::ENCODED_LEN] = data.try_into().map_err(|_| Error::InvalidLength)?;
+        let rotation = match data[0] {
+            0 => Rotation::Rotate0,
+            1 => Rotation::Rotate90,
+            2 => Rotation::Rotate180,
+            3 => Rotation::Rotate270,
+            _ => return Err(Error::InvalidValue),
+        };
+        Ok(OrientationConfig {
+            rotation,
+            mirrored: data[1] != 0,
+        })
+    }
+
+    /// Size of the frame as seen by the hub.
+    pub fn size(self) -> Size {
+        match self.rotation {
+            Rotation::Rotate0 | Rotation::Rotate180 => Size::new(WIDTH, HEIGHT),
+            Rotation::Rotate90 | Rotation::Rotate270 => Size::new(HEIGHT, WIDTH),
+        }
+    }
+
+    /// Position in the frame buffer of `point` as seen by the hub.
+    pub fn to_physical(self, point: Point) -> Point {
+        let (width, height) = (WIDTH as i32, HEIGHT as i32);
+        let x = if self.mirrored {
+            self.size().width as i32 - 1 - point.x
+        } else {
+            point.x
+        };
+        let y = point.y;
+        match self.rotation {
+            Rotation::Rotate0 => Point::new(x, y),
+            Rotation::Rotate90 => Point::new(width - 1 - y, x),
+            Rotation::Rotate180 => Point::new(width - 1 - x, height - 1 - y),
+            Rotation::Rotate270 => Point::new(y, height - 1 - x),
+        }
+    }
+
+    /// Area of the frame buffer covered by `area` as seen by the hub.
+    pub fn to_physical_area(self, area: Rectangle) -> Rectangle {
+        let Some(bottom_right) = area.bottom_right() else {
+            return Rectangle::zero();
+        };
+        let a = self.to_physical(area.top_left);
+        let b = self.to_physical(bottom_right);
+        Rectangle::with_corners(a, b)
+    }
+}
+
+/// Frame buffer drawn on in the configured orientation.
+pub struct Canvas<'a> {
+    display: &'a mut Display7in5,
+    orientation: OrientationConfig,
+}
+
+impl OriginDimensions for Canvas<'_> {
+    fn size(&self) -> Size {
+        self.orientation.size()
+    }
+}
+
+impl DrawTarget for Canvas<'_> {
+    type Color = TriColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bounds = self.bounding_box();
+        let orientation = self.orientation;
+        let pixels = pixels
+            .into_iter()
+            .filter(|Pixel(point, _)| bounds.contains(*point))
+            .map(|Pixel(point, color)| Pixel(orientation.to_physical(point), color));
+        self.display.draw_iter(pixels)
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        // shapes from the hub can be far larger than the frame, only visit the visible part
+        let area = area.intersection(&self.bounding_box());
+        self.display
+            .fill_solid(&self.orientation.to_physical_area(area), color)
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.display.clear(color)
+    }
+}
+
+/// The frame buffer, holding the panel's native landscape frame in the layout the panel
+/// expects.
+pub struct Frame {
+    display: Display7in5,
+    orientation: OrientationConfig,
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        let mut display = Display7in5::default();
+        let Ok(()) = display.clear(TriColor::White);
+        Frame {
+            display,
+            orientation: OrientationConfig::default(),
+        }
+    }
+}
+
+impl Frame {
+    /// Write the 128 pixels of chunk `cursor`, counting rows of the frame in the
+    /// configured orientation.
+    pub fn write_to_buffer(&mut self, values: &[u8; CHUNK_LEN], cursor: u32) -> Result<(), Error> {
+        if cursor >= CHUNK_COUNT {
+            return Err(Error::CursorOutOfRange);
+        }
+        let colors = bytes_to_color(values);
+        let display_width = self.orientation.size().width;
+        let pixel_cursor = cursor * chunk::CHUNK_PIXELS as u32;
+
+        // chunks wrap around to the next row
+        let pixels = colors
+            .into_iter()
+            .zip(pixel_cursor..)
+            .map(|(color, index)| {
+                let point = Point::new(
+                    (index % display_width) as i32,
+                    (index / display_width) as i32,
+                );
+                Pixel(point, color)
+            });
+        let Ok(()) = self.canvas().draw_iter(pixels);
+        Ok(())
+    }
+
+    /// Both bit planes, see [`color_at`] for the bits of each color.
+    pub fn buffer(&self) -> &[u8] {
+        self.display.buffer()
+    }
+
+    /// Replace the frame with `buffer`, laid out like [`Self::buffer`].
+    ///
+    /// The driver doesn't hand out its buffer mutably, so the planes are decoded into
+    /// pixels.
+    pub fn load_buffer(&mut self, buffer: &[u8]) -> Result<(), Error> {
+        if buffer.len() != FRAME_LEN {
+            return Err(Error::InvalidLength);
+        }
+        for y in 0..HEIGHT as i32 {
+            for x in 0..WIDTH as i32 {
+                let point = Point::new(x, y);
+                self.display
+                    .set_pixel(Pixel(point, color_at(buffer, point)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Frame buffer to draw on with embedded-graphics, in the configured orientation.
+    pub fn canvas(&mut self) -> Canvas<'_> {
+        Canvas {
+            display: &mut self.display,
+            orientation: self.orientation,
+        }
+    }
+
+    pub fn orientation(&self) -> OrientationConfig {
+        self.orientation
+    }
+
+    /// Change the orientation of everything drawn from now on, the frame buffer is kept.
+    pub fn set_orientation(&mut self, orientation: OrientationConfig) {
+        self.orientation = orientation;
+    }
+
+    /// FNV-1a hash of the frame buffer, used to identify the displayed image.
+    pub fn hash(&self) -> u32 {
+        fnv1a(self.display.buffer())
+    }
+
+    /// Keep the pixels in `area`, as seen by the hub, before something is drawn on top of
+    /// them, so they can be brought back by [`Self::uncover`].
+    pub fn cover<const LEN: usize>(
+        &self,
+        covered: &mut Covered<LEN>,
+        area: Rectangle,
+    ) -> Result<(), Error> {
+        let bounds = Rectangle::new(Point::zero(), Size::new(WIDTH, HEIGHT));
+        let area = self
+            .orientation
+            .to_physical_area(area)
+            .intersection(&bounds);
+        if area.size.width as usize * area.size.height as usize > LEN * 4 {
+            return Err(Error::InvalidLength);
+        }
+        covered.pixels.fill(0);
+        for (index, point) in area.points().enumerate() {
+            let bits = match color_at(self.display.buffer(), point) {
+                TriColor::Black => 0,
+                TriColor::White => 1,
+                TriColor::Chromatic => 2,
+            };
+            covered.pixels[index / 4] |= bits << (index % 4 * 2);
+        }
+        covered.area = Some(area);
+        Ok(())
+    }
+
+    /// Bring back the pixels kept by [`Self::cover`], if any.
+    pub fn uncover<const LEN: usize>(&mut self, covered: &mut Covered<LEN>) {
+        let Some(area) = covered.area.take() else {
+            return;
+        };
+        for (index, point) in area.points().enumerate() {
+            let color = match covered.pixels[index / 4] >> (index % 4 * 2) & 0b11 {
+                0 => TriColor::Black,
+                1 => TriColor::White,
+                _ => TriColor::Chromatic,
+            };
+            self.display.set_pixel(Pixel(point, color));
+        }
+    }
+}
+
+/// Pixels of the frame covered by something drawn on top of it, e.g. the dashboard's
+/// widget, kept so the frame can be shown without it again.
+///
+/// Holds up to `LEN * 4` pixels of two bits each.
+pub struct Covered<const LEN: usize> {
+    /// Area of the frame buffer, in the panel's native orientation
+    area: Option<Rectangle>,
+    pixels: [u8; LEN],
+}
+
+impl<const LEN: usize> Default for Covered<LEN> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const LEN: usize> Covered<LEN> {
+    pub const fn new() -> Self {
+        Covered {
+            area: None,
+            pixels: [0; LEN],
+        }
+    }
+
+    /// Forget the kept pixels, e.g. because a new frame replaced them.
+    pub fn forget(&mut self) {
+        self.area = None;
+    }
+}
+
+/// Color of the pixel at `point` of the panel's native frame in `buffer`, laid out like
+/// [`Frame::buffer`].
+///
+/// A set bit in the chromatic plane takes precedence, otherwise the black/white plane
+/// holds a set bit for white pixels.
+pub fn color_at(buffer: &[u8], point: Point) -> TriColor {
+    let index = point.y as usize * ROW_LEN + point.x as usize / 8;
+    let mask = 0x80 >> (point.x % 8);
+    if buffer[PLANE_LEN + index] & mask != 0 {
+        TriColor::Chromatic
+    } else if buffer[index] & mask != 0 {
+        TriColor::White
+    } else {
+        TriColor::Black
+    }
+}
+
+/// Decode the 128 pixels of a chunk, see [`periphery_protocol::chunk`].
+pub fn bytes_to_color(bytes: &[u8; CHUNK_LEN]) -> [TriColor; chunk::CHUNK_PIXELS] {
+    chunk::decode(bytes).map(|color| match color {
+        Color::Black => TriColor::Black,
+        Color::White => TriColor::White,
+        Color::Chromatic => TriColor::Chromatic,
+    })
+}
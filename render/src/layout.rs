@@ -0,0 +1,325 @@
+use embedded_graphics::{
+    mono_font::{
+        MonoFont, MonoTextStyle,
+        ascii::{FONT_6X10, FONT_8X13, FONT_10X20},
+    },
+    prelude::*,
+    primitives::{Line, PrimitiveStyleBuilder, Rectangle},
+    text::{Baseline, Text},
+};
+use epd_waveshare::color::TriColor;
+
+use crate::Error;
+use crate::font::StoredFont;
+use crate::qr;
+
+const COMMAND_CLEAR: u8 = 0x01;
+const COMMAND_LINE: u8 = 0x02;
+const COMMAND_RECTANGLE: u8 = 0x03;
+const COMMAND_TEXT: u8 = 0x04;
+const COMMAND_ICON: u8 = 0x05;
+const COMMAND_QR_CODE: u8 = 0x06;
+
+/// Fill color of rectangles that are only outlined
+const NO_FILL: u8 = 0xFF;
+const TEXT_WRAP: u8 = 1 << 0;
+/// Text font ids from here on select an uploaded font
+const STORED_FONT: u8 = 0x10;
+
+#[derive(Clone, Copy)]
+enum TextFont<'a> {
+    Mono(&'static MonoFont<'static>),
+    Stored(StoredFont<'a>),
+}
+
+impl TextFont<'_> {
+    fn height(&self) -> u32 {
+        match self {
+            TextFont::Mono(font) => font.character_size.height,
+            TextFont::Stored(font) => font.height(),
+        }
+    }
+
+    fn width(&self, text: &str) -> u32 {
+        match self {
+            TextFont::Mono(font) => {
+                let advance = font.character_size.width + font.character_spacing;
+                text.chars().count() as u32 * advance
+            }
+            TextFont::Stored(font) => text.chars().map(|c| font.advance(c)).sum(),
+        }
+    }
+
+    fn draw<D>(
+        &self,
+        target: &mut D,
+        text: &str,
+        position: Point,
+        color: TriColor,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = TriColor>,
+    {
+        match self {
+            TextFont::Mono(font) => {
+                let style = MonoTextStyle::new(font, color);
+                Text::with_baseline(text, position, style, Baseline::Top).draw(target)?;
+                Ok(())
+            }
+            TextFont::Stored(font) => font.draw(target, text, position, color),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Alignment {
+    Left,
+    Center,
+    Right,
+}
+
+/// Draw a sequence of commands, each starting with its command byte. Coordinates are
+/// signed (i16), sizes unsigned (u16), colors are 0 for white, 1 for black and 2 for
+/// chromatic, all little endian:
+///
+/// - clear: `[color]`
+/// - line: `[x0, y0, x1, y1, stroke width, color]`
+/// - rectangle: `[x, y, width, height, stroke width, stroke color, fill color (0xFF for none)]`
+/// - text: `[x, y, width, height, font (0 to 2 built in, growing, 0x10 + n uploaded font n),
+///   color, alignment (0 left, 1 center, 2 right), flags (bit 0 wraps words), text length,
+///   text]`
+/// - icon: `[x, y, width, height, color, 1 bit per pixel rows, each padded to whole bytes]`
+/// - QR code: `[x, y, module size (pixels), color, error correction (0 low to 3 high),
+///   text length, text]`
+///
+/// Uploaded fonts are looked up with `fonts`.
+pub fn render<'a, D>(
+    target: &mut D,
+    commands: &[u8],
+    fonts: impl Fn(usize) -> Option<StoredFont<'a>>,
+) -> Result<(), Error>
+where
+    D: DrawTarget<Color = TriColor>,
+{
+    let mut reader = Reader { data: commands };
+    while let Ok(command) = reader.u8() {
+        let result = match command {
+            COMMAND_CLEAR => {
+                let color = reader.color()?;
+                target.clear(color)
+            }
+            COMMAND_LINE => {
+                let start = reader.point()?;
+                let end = reader.point()?;
+                let width = reader.u8()?;
+                let color = reader.color()?;
+                // embedded-graphics computes `(2 * width)² * length²` in an i32
+                let delta = end - start;
+                let length_squared = i64::from(delta.x).pow(2) + i64::from(delta.y).pow(2);
+                if (2 * i64::from(width)).pow(2) * length_squared > i64::from(i32::MAX) {
+                    return Err(Error::InvalidValue);
+                }
+                Line::new(start, end)
+                    .into_styled(
+                        PrimitiveStyleBuilder::new()
+                            .stroke_width(width.into())
+                            .stroke_color(color)
+                            .build(),
+                    )
+                    .draw(target)
+            }
+            COMMAND_RECTANGLE => {
+                let area = reader.rectangle()?;
+                let width = reader.u8()?;
+                let stroke = reader.color()?;
+                let mut style = PrimitiveStyleBuilder::new()
+                    .stroke_width(width.into())
+                    .stroke_color(stroke);
+                if reader.peek()? == NO_FILL {
+                    reader.u8()?;
+                } else {
+                    style = style.fill_color(reader.color()?);
+                }
+                area.into_styled(style.build()).draw(target)
+            }
+            COMMAND_TEXT => {
+                let area = reader.rectangle()?;
+                let font = match reader.u8()? {
+                    0 => TextFont::Mono(&FONT_6X10),
+                    1 => TextFont::Mono(&FONT_8X13),
+                    2 => TextFont::Mono(&FONT_10X20),
+                    id @ STORED_FONT.. => {
+                        let font = fonts((id - STORED_FONT).into());
+                        TextFont::Stored(font.ok_or(Error::InvalidValue)?)
+                    }
+                    _ => return Err(Error::InvalidValue),
+                };
+                let color = reader.color()?;
+                let alignment = match reader.u8()? {
+                    0 => Alignment::Left,
+                    1 => Alignment::Center,
+                    2 => Alignment::Right,
+                    _ => return Err(Error::InvalidValue),
+                };
+                let wrap = reader.u8()? & TEXT_WRAP != 0;
+                let len = reader.u8()?;
+                let text = core::str::from_utf8(reader.bytes(len.into())?)
+                    .map_err(|_| Error::InvalidValue)?;
+                draw_text(target, area, font, color, alignment, wrap, text)
+            }
+            COMMAND_ICON => {
+                let area = reader.rectangle()?;
+                let color = reader.color()?;
+                let row_len = (area.size.width as usize).div_ceil(8);
+                let data = reader.bytes(row_len * area.size.height as usize)?;
+                // cleared bits are transparent
+                let pixels = area.points().filter_map(|point| {
+                    let offset = point - area.top_left;
+                    let (x, y) = (offset.x as usize, offset.y as usize);
+                    let set = data[y * row_len + x / 8] & (0x80 >> (x % 8)) != 0;
+                    set.then_some(Pixel(point, color))
+                });
+                target.draw_iter(pixels)
+            }
+            COMMAND_QR_CODE => {
+                let top_left = reader.point()?;
+                let scale = reader.u8()?;
+                let color = reader.color()?;
+                let ecc = reader.u8()?;
+                let len = reader.u8()?;
+                let text = core::str::from_utf8(reader.bytes(len.into())?)
+                    .map_err(|_| Error::InvalidValue)?;
+                qr::draw(target, text, top_left, scale, color, ecc)?;
+                Ok(())
+            }
+            _ => return Err(Error::InvalidValue),
+        };
+        // drawing only fails for targets other than the frame buffer
+        if result.is_err() {
+            return Err(Error::InvalidValue);
+        }
+    }
+    Ok(())
+}
+
+/// Draw `text` line by line into `area`, cutting off lines that don't fit.
+fn draw_text<D>(
+    target: &mut D,
+    area: Rectangle,
+    font: TextFont<'_>,
+    color: TriColor,
+    alignment: Alignment,
+    wrap: bool,
+    text: &str,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = TriColor>,
+{
+    let mut target = target.clipped(&area);
+
+    let mut y = area.top_left.y;
+    let bottom = area.top_left.y + area.size.height as i32;
+    for paragraph in text.split('\n') {
+        let mut rest = paragraph;
+        loop {
+            if y + font.height() as i32 > bottom {
+                return Ok(());
+            }
+            let (line, next) = if wrap {
+                split_line(rest, &font, area.size.width)
+            } else {
+                (rest, "")
+            };
+            let width = font.width(line) as i32;
+            let x = match alignment {
+                Alignment::Left => area.top_left.x,
+                Alignment::Center => area.top_left.x + (area.size.width as i32 - width) / 2,
+                Alignment::Right => area.top_left.x + area.size.width as i32 - width,
+            };
+            font.draw(&mut target, line, Point::new(x, y), color)?;
+            y += font.height() as i32;
+
+            rest = next;
+            if rest.is_empty() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Split off the first line at most `width` pixels wide, breaking at a space if possible.
+fn split_line<'a>(text: &'a str, font: &TextFont<'_>, width: u32) -> (&'a str, &'a str) {
+    let mut line_width = 0;
+    let end = text
+        .char_indices()
+        .find(|&(index, c)| {
+            line_width += font.width(&text[index..index + c.len_utf8()]);
+            // keep at least one character per line
+            line_width > width && index > 0
+        })
+        .map_or(text.len(), |(index, _)| index);
+    if end == text.len() {
+        return (text, "");
+    }
+    if text[end..].starts_with(' ') {
+        return (&text[..end], &text[end + 1..]);
+    }
+    match text[..end].rfind(' ') {
+        Some(space) if space > 0 => (&text[..space], &text[space + 1..]),
+        // a single word longer than the line is broken up
+        _ => (&text[..end], &text[end..]),
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.data.len() < len {
+            return Err(Error::InvalidLength);
+        }
+        let (value, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(value)
+    }
+
+    fn peek(&self) -> Result<u8, Error> {
+        self.data.first().copied().ok_or(Error::InvalidLength)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn i16(&mut self) -> Result<i16, Error> {
+        let bytes = self.bytes(2)?;
+        Ok(i16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn point(&mut self) -> Result<Point, Error> {
+        Ok(Point::new(self.i16()?.into(), self.i16()?.into()))
+    }
+
+    fn rectangle(&mut self) -> Result<Rectangle, Error> {
+        let top_left = self.point()?;
+        let size = Size::new(self.u16()?.into(), self.u16()?.into());
+        Ok(Rectangle::new(top_left, size))
+    }
+
+    fn color(&mut self) -> Result<TriColor, Error> {
+        match self.u8()? {
+            0 => Ok(TriColor::White),
+            1 => Ok(TriColor::Black),
+            2 => Ok(TriColor::Chromatic),
+            _ => Err(Error::InvalidValue),
+        }
+    }
+}
@@ -0,0 +1,14 @@
+//! Drawing into the frame buffer of the dashboard, without depending on the e-ink panel.
+//!
+//! The firmware shows frames on the Waveshare panel, the simulator renders them to PNG,
+//! both through a [`screen::Panel`].
+#![no_std]
+
+pub mod font;
+pub mod frame;
+pub mod layout;
+pub mod qr;
+pub mod screen;
+pub mod widget;
+
+pub use periphery_protocol::Error;
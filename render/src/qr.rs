@@ -0,0 +1,79 @@
+use embedded_graphics::{
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+};
+use epd_waveshare::color::TriColor;
+use qrcodegen_no_heap::{QrCode, QrCodeEcc, Version};
+
+use crate::Error;
+
+/// Largest symbol generated, holding e.g. 213 bytes with medium error correction
+const MAX_VERSION: Version = Version::new(10);
+const BUFFER_LEN: usize = MAX_VERSION.buffer_len();
+/// Light modules required around the symbol, so it can be scanned
+const QUIET_ZONE: i32 = 4;
+
+/// Encode `text` as QR code and draw it with its quiet zone at `top_left`, each module
+/// `scale` pixels wide.
+///
+/// `ecc` selects the error correction level from 0 (low) to 3 (high).
+pub fn draw<D>(
+    target: &mut D,
+    text: &str,
+    top_left: Point,
+    scale: u8,
+    color: TriColor,
+    ecc: u8,
+) -> Result<(), Error>
+where
+    D: DrawTarget<Color = TriColor>,
+{
+    let ecc = match ecc {
+        0 => QrCodeEcc::Low,
+        1 => QrCodeEcc::Medium,
+        2 => QrCodeEcc::Quartile,
+        3 => QrCodeEcc::High,
+        _ => return Err(Error::InvalidValue),
+    };
+    if scale == 0 {
+        return Err(Error::InvalidValue);
+    }
+    let mut temp = [0u8; BUFFER_LEN];
+    let mut out = [0u8; BUFFER_LEN];
+    let code = QrCode::encode_text(
+        text,
+        &mut temp,
+        &mut out,
+        ecc,
+        Version::MIN,
+        MAX_VERSION,
+        None,
+        true,
+    )
+    // the text doesn't fit into the largest symbol
+    .map_err(|_| Error::InvalidLength)?;
+
+    let scale = i32::from(scale);
+    let side = ((code.size() + 2 * QUIET_ZONE) * scale) as u32;
+    let area = Rectangle::new(top_left, Size::new(side, side));
+    let modules = (0..code.size())
+        .flat_map(|y| (0..code.size()).map(move |x| (x, y)))
+        .filter(|&(x, y)| code.get_module(x, y))
+        .flat_map(|(x, y)| {
+            let origin = top_left + Point::new(x + QUIET_ZONE, y + QUIET_ZONE) * scale;
+            Rectangle::new(origin, Size::new(scale as u32, scale as u32))
+                .points()
+                .map(move |point| Pixel(point, color))
+        });
+
+    let Ok(()) = area
+        .into_styled(PrimitiveStyle::with_fill(TriColor::White))
+        .draw(target)
+    else {
+        return Err(Error::InvalidValue);
+    };
+    let Ok(()) = target.draw_iter(modules) else {
+        return Err(Error::InvalidValue);
+    };
+    Ok(())
+}
@@ -0,0 +1,244 @@
+use embedded_graphics::{
+    mono_font::{MonoTextStyleBuilder, ascii::FONT_10X20},
+    prelude::*,
+    primitives::Rectangle,
+    text::{Baseline, Text},
+};
+use epd_waveshare::{
+    color::TriColor,
+    epd7in5b_v2::{HEIGHT, WIDTH},
+};
+use periphery_protocol::hash::{fnv1a, fnv1a_update};
+
+use crate::frame::{FRAME_LEN, Frame, PLANE_LEN, ROW_LEN};
+
+/// Side of the square tiles compared to find the changed part of a frame
+const TILE_SIZE: u32 = 32;
+const TILE_COLUMNS: usize = (WIDTH / TILE_SIZE) as usize;
+const TILE_ROWS: usize = (HEIGHT / TILE_SIZE) as usize;
+/// Largest window refreshed partially, holding both bit planes. Larger changes take a
+/// full refresh, which isn't much slower by then.
+const WINDOW_MAX_LEN: usize = FRAME_LEN / 4;
+
+/// Shows frames, e.g. the e-ink panel or the simulator.
+pub trait Panel {
+    type Error;
+
+    /// Show `frame`, laid out like [`Frame::buffer`], refreshing the whole panel.
+    fn show(&mut self, frame: &[u8]) -> Result<(), Self::Error>;
+
+    /// Refresh only `area` of the panel, in which only black and white pixels changed.
+    ///
+    /// `window` holds the rows of `area` of the black/white plane followed by those of the
+    /// chromatic plane. The area is aligned to whole bytes.
+    fn show_window(&mut self, window: &[u8], area: Rectangle) -> Result<(), Self::Error>;
+
+    /// Clear the panel to white.
+    fn clear(&mut self) -> Result<(), Self::Error>;
+}
+
+/// Hashes of the tiles of a frame, per bit plane, so changes can be found without keeping
+/// a copy of the frame shown on the panel.
+#[derive(Clone, Copy)]
+struct TileHashes {
+    black: [u32; TILE_COLUMNS * TILE_ROWS],
+    chromatic: [u32; TILE_COLUMNS * TILE_ROWS],
+}
+
+impl TileHashes {
+    fn of(frame: &[u8]) -> Self {
+        let (black, chromatic) = frame.split_at(PLANE_LEN);
+        TileHashes {
+            black: core::array::from_fn(|tile| Self::hash(black, tile)),
+            chromatic: core::array::from_fn(|tile| Self::hash(chromatic, tile)),
+        }
+    }
+
+    fn hash(plane: &[u8], tile: usize) -> u32 {
+        let tile_len = TILE_SIZE as usize / 8;
+        let start =
+            (tile / TILE_COLUMNS) * TILE_SIZE as usize * ROW_LEN + (tile % TILE_COLUMNS) * tile_len;
+        (0..TILE_SIZE as usize).fold(fnv1a(&[]), |hash, row| {
+            let offset = start + row * ROW_LEN;
+            fnv1a_update(hash, &plane[offset..offset + tile_len])
+        })
+    }
+
+    /// Part of the frame buffer that differs from `previous`, `None` if nothing changed.
+    fn changes(&self, previous: &TileHashes) -> Option<FrameChange> {
+        let mut area: Option<(Point, Point)> = None;
+        let mut chromatic = false;
+        for tile in 0..TILE_COLUMNS * TILE_ROWS {
+            let chromatic_changed = self.chromatic[tile] != previous.chromatic[tile];
+            if self.black[tile] == previous.black[tile] && !chromatic_changed {
+                continue;
+            }
+            chromatic |= chromatic_changed;
+            let corner = Point::new((tile % TILE_COLUMNS) as i32, (tile / TILE_COLUMNS) as i32);
+            area = Some(match area {
+                Some((min, max)) => (min.component_min(corner), max.component_max(corner)),
+                None => (corner, corner),
+            });
+        }
+        let (min, max) = area?;
+        let tile = TILE_SIZE as i32;
+        Some(FrameChange {
+            area: Rectangle::with_corners(
+                min * tile,
+                (max + Point::new(1, 1)) * tile - Point::new(1, 1),
+            ),
+            chromatic,
+        })
+    }
+}
+
+/// How the panel was updated by [`Screen::display_buffer`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Refresh {
+    Full,
+    /// Only the changed window of the panel was refreshed
+    Partial,
+    /// The panel already showed the frame, so it wasn't refreshed
+    Unchanged,
+}
+
+/// Part of the frame buffer changed since it was last sent to the panel.
+struct FrameChange {
+    /// Bounding box of the changed tiles
+    area: Rectangle,
+    /// Whether chromatic pixels changed, which always takes the full tri-color waveform
+    chromatic: bool,
+}
+
+/// A frame buffer and the panel it is shown on.
+pub struct Screen<P> {
+    panel: P,
+    frame: Frame,
+    /// Tiles of the frame in the panel's memory, `None` if unknown
+    shown: Option<TileHashes>,
+    /// Both bit planes of the window refreshed partially, in the layout the panel expects
+    window: [u8; WINDOW_MAX_LEN],
+}
+
+impl<P: Panel> Screen<P> {
+    /// Screen with a white frame, the panel's content is unknown until it is refreshed.
+    pub fn new(panel: P) -> Self {
+        Screen {
+            panel,
+            frame: Frame::default(),
+            shown: None,
+            window: [0; WINDOW_MAX_LEN],
+        }
+    }
+
+    pub fn panel(&self) -> &P {
+        &self.panel
+    }
+
+    pub fn panel_mut(&mut self) -> &mut P {
+        &mut self.panel
+    }
+
+    pub fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    pub fn frame_mut(&mut self) -> &mut Frame {
+        &mut self.frame
+    }
+
+    /// Clear the frame buffer and the panel to white.
+    pub fn clear(&mut self) -> Result<(), P::Error> {
+        let Ok(()) = self.frame.canvas().clear(TriColor::White);
+        // the panel's memory is undefined if clearing fails half way
+        self.shown = None;
+        self.panel.clear()
+    }
+
+    /// Drive the whole panel to `color`, e.g. during a clean cycle, even if it already
+    /// shows it. The frame buffer is overwritten, so the shown frame has to be loaded again
+    /// afterwards.
+    pub fn fill_panel(&mut self, color: TriColor) -> Result<(), P::Error> {
+        let Ok(()) = self.frame.canvas().clear(color);
+        // the panel no longer shows a frame the next one can be compared to
+        self.shown = None;
+        self.panel.show(self.frame.buffer())
+    }
+
+    /// Show the frame buffer on the panel.
+    ///
+    /// If only black and white pixels changed, only the window around them is refreshed,
+    /// so e.g. the clock widget doesn't flash the whole panel. Chromatic pixels need the
+    /// full refresh to settle without bleeding into their surroundings.
+    ///
+    /// Frames the panel already shows are skipped, saving the flashing and the energy of a
+    /// refresh when the hub pushes the same image again.
+    pub fn display_buffer(&mut self) -> Result<Refresh, P::Error> {
+        let tiles = TileHashes::of(self.frame.buffer());
+        let change = match &self.shown {
+            Some(shown) => tiles.changes(shown),
+            None => Some(FrameChange {
+                area: Rectangle::new(Point::zero(), Size::new(WIDTH, HEIGHT)),
+                chromatic: true,
+            }),
+        };
+        let Some(change) = change else {
+            return Ok(Refresh::Unchanged);
+        };
+        // the panel's memory is undefined if the refresh fails half way
+        self.shown = None;
+        let refresh = match change {
+            FrameChange {
+                area,
+                chromatic: false,
+            } if window_len(area) <= WINDOW_MAX_LEN => {
+                let len = self.copy_window(area);
+                self.panel.show_window(&self.window[..len], area)?;
+                Refresh::Partial
+            }
+            _ => {
+                self.panel.show(self.frame.buffer())?;
+                Refresh::Full
+            }
+        };
+        self.shown = Some(tiles);
+        Ok(refresh)
+    }
+
+    /// Copy `area` of both bit planes into the window buffer, returning its length.
+    ///
+    /// The area has to be aligned to whole bytes, as the changed tiles are.
+    fn copy_window(&mut self, area: Rectangle) -> usize {
+        let row_len = area.size.width as usize / 8;
+        let rows = area.size.height as usize;
+        let start = area.top_left.y as usize * ROW_LEN + area.top_left.x as usize / 8;
+        let (black, chromatic) = self.frame.buffer().split_at(PLANE_LEN);
+        for (index, plane) in [black, chromatic].into_iter().enumerate() {
+            for row in 0..rows {
+                let from = start + row * ROW_LEN;
+                let to = (index * rows + row) * row_len;
+                self.window[to..to + row_len].copy_from_slice(&plane[from..from + row_len]);
+            }
+        }
+        window_len(area)
+    }
+
+    pub fn display_text(&mut self) -> Result<(), P::Error> {
+        let text_style = MonoTextStyleBuilder::new()
+            .font(&FONT_10X20)
+            .text_color(TriColor::Black)
+            .build();
+
+        let Ok(_) = Text::with_baseline("Test", Point::new(100, 100), text_style, Baseline::Top)
+            .draw(&mut self.frame.canvas());
+
+        self.display_buffer()?;
+        Ok(())
+    }
+}
+
+/// Length of both bit planes of `area`.
+fn window_len(area: Rectangle) -> usize {
+    area.size.width as usize / 8 * area.size.height as usize * 2
+}
@@ -0,0 +1,95 @@
+use embedded_graphics::{
+    mono_font::{
+        MonoFont,
+        ascii::{FONT_6X10, FONT_10X20},
+    },
+    prelude::*,
+};
+
+use crate::Error;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WidgetMode {
+    Off,
+    Time,
+    Date,
+    DateTime,
+}
+
+/// Clock and date drawn by the device on top of the image, so the hub doesn't have to
+/// push a new image just to update the time.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct WidgetConfig {
+    pub mode: WidgetMode,
+    /// Only update the time every hour, as each update refreshes the display
+    pub hourly: bool,
+    /// Top left corner of the widget
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub position: Point,
+    pub large: bool,
+    pub chromatic: bool,
+}
+
+impl Default for WidgetConfig {
+    fn default() -> Self {
+        WidgetConfig {
+            mode: WidgetMode::Off,
+            hourly: false,
+            position: Point::zero(),
+            large: false,
+            chromatic: false,
+        }
+    }
+}
+
+impl WidgetConfig {
+    /// Encoded as `[mode (0 off, 1 time, 2 date, 3 date and time), hourly, x (u16), y (u16),
+    /// large font, chromatic]`, little endian.
+    pub const ENCODED_LEN: usize = 8;
+
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut encoded = [0u8; Self::ENCODED_LEN];
+        encoded[0] = match self.mode {
+            WidgetMode::Off => 0,
+            WidgetMode::Time => 1,
+            WidgetMode::Date => 2,
+            WidgetMode::DateTime => 3,
+        };
+        encoded[1] = self.hourly as u8;
+        encoded[2..4].copy_from_slice(&(self.position.x as u16).to_le_bytes());
+        encoded[4..6].copy_from_slice(&(self.position.y as u16).to_le_bytes());
+        encoded[6] = self.large as u8;
+        encoded[7] = self.chromatic as u8;
+        encoded
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let data: &[u8; Self::ENCODED_LEN] = data.try_into().map_err(|_| Error::InvalidLength)?;
+        let mode = match data[0] {
+            0 => WidgetMode::Off,
+            1 => WidgetMode::Time,
+            2 => WidgetMode::Date,
+            3 => WidgetMode::DateTime,
+            _ => return Err(Error::InvalidValue),
+        };
+        let x = u16::from_le_bytes([data[2], data[3]]);
+        let y = u16::from_le_bytes([data[4], data[5]]);
+        // either side may be the long one, depending on the orientation
+        if x >= 800 || y >= 800 {
+            return Err(Error::InvalidValue);
+        }
+        Ok(WidgetConfig {
+            mode,
+            hourly: data[1] != 0,
+            position: Point::new(x.into(), y.into()),
+            large: data[6] != 0,
+            chromatic: data[7] != 0,
+        })
+    }
+
+    pub fn font(&self) -> &'static MonoFont<'static> {
+        if self.large { &FONT_10X20 } else { &FONT_6X10 }
+    }
+}
@@ -0,0 +1,114 @@
+//! Simulates the dashboard's e-ink panel on the host, so what the firmware draws can be
+//! looked at as PNG and checked by tests without flashing hardware.
+
+use std::convert::Infallible;
+use std::io::Write;
+
+use embedded_graphics::{prelude::*, primitives::Rectangle};
+use epd_waveshare::{
+    color::TriColor,
+    epd7in5b_v2::{HEIGHT, WIDTH},
+};
+use periphery_render::frame::{FRAME_LEN, PLANE_LEN, ROW_LEN, color_at};
+use periphery_render::screen::Panel;
+
+/// RGB colors of the palette indices used in the PNG: white, black and chromatic (red)
+const PALETTE: [u8; 9] = [0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0xC0, 0x10, 0x10];
+
+/// Panel keeping a copy of the frame it shows, updated like the panel's memory.
+pub struct SimulatedPanel {
+    memory: Vec<u8>,
+    full_refreshes: u32,
+    partial_refreshes: u32,
+}
+
+impl Default for SimulatedPanel {
+    fn default() -> Self {
+        SimulatedPanel {
+            memory: white_frame(),
+            full_refreshes: 0,
+            partial_refreshes: 0,
+        }
+    }
+}
+
+impl SimulatedPanel {
+    /// The frame shown, laid out like `Frame::buffer`.
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// Color shown at `point` of the panel's native landscape frame.
+    pub fn pixel(&self, point: Point) -> TriColor {
+        color_at(&self.memory, point)
+    }
+
+    pub fn full_refreshes(&self) -> u32 {
+        self.full_refreshes
+    }
+
+    pub fn partial_refreshes(&self) -> u32 {
+        self.partial_refreshes
+    }
+
+    /// Write the frame shown as indexed PNG, in the panel's native landscape orientation.
+    pub fn write_png<W: Write>(&self, writer: W) -> Result<(), png::EncodingError> {
+        write_png(&self.memory, writer)
+    }
+}
+
+impl Panel for SimulatedPanel {
+    type Error = Infallible;
+
+    fn show(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        self.memory.copy_from_slice(frame);
+        self.full_refreshes += 1;
+        Ok(())
+    }
+
+    fn show_window(&mut self, window: &[u8], area: Rectangle) -> Result<(), Self::Error> {
+        let row_len = area.size.width as usize / 8;
+        let rows = area.size.height as usize;
+        let start = area.top_left.y as usize * ROW_LEN + area.top_left.x as usize / 8;
+        for (index, plane) in window.chunks_exact(row_len * rows).enumerate() {
+            for (row, data) in plane.chunks_exact(row_len).enumerate() {
+                let offset = index * PLANE_LEN + start + row * ROW_LEN;
+                self.memory[offset..offset + row_len].copy_from_slice(data);
+            }
+        }
+        self.partial_refreshes += 1;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.memory = white_frame();
+        Ok(())
+    }
+}
+
+/// Write `frame`, laid out like `Frame::buffer`, as indexed PNG with one byte per pixel:
+/// 0 for white, 1 for black and 2 for chromatic pixels.
+pub fn write_png<W: Write>(frame: &[u8], writer: W) -> Result<(), png::EncodingError> {
+    let mut encoder = png::Encoder::new(writer, WIDTH, HEIGHT);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(&PALETTE[..]);
+    let mut writer = encoder.write_header()?;
+    let pixels: Vec<u8> = (0..HEIGHT as i32)
+        .flat_map(|y| (0..WIDTH as i32).map(move |x| Point::new(x, y)))
+        .map(|point| match color_at(frame, point) {
+            TriColor::White => 0,
+            TriColor::Black => 1,
+            TriColor::Chromatic => 2,
+        })
+        .collect();
+    writer.write_image_data(&pixels)?;
+    writer.finish()
+}
+
+/// White in both bit planes, what a cleared panel shows.
+fn white_frame() -> Vec<u8> {
+    let mut frame = vec![0xFF; FRAME_LEN];
+    frame[PLANE_LEN..].fill(0x00);
+    frame
+}
@@ -0,0 +1,132 @@
+//! Render chunk streams and layouts the way the dashboard would show them.
+//!
+//! ```text
+//! periphery_simulator [--rotation 0|90|180|270] [--mirrored] [--chunks FILE]
+//!     [--layout FILE] [--font FILE]... OUTPUT.png
+//! ```
+//!
+//! - `--chunks`: encoded frame as written to `write_buffer`, chunk after chunk from the
+//!   first one
+//! - `--layout`: drawing commands as written to the layout characteristic, starting with
+//!   their total length (u16), drawn on a white frame
+//! - `--font`: uploaded font, selected by layouts as font `0x10 + n` for the n-th font
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::process::ExitCode;
+
+use embedded_graphics::prelude::*;
+use epd_waveshare::color::TriColor;
+use periphery_render::font::StoredFont;
+use periphery_render::frame::{CHUNK_LEN, OrientationConfig, Rotation};
+use periphery_render::layout;
+use periphery_render::screen::Screen;
+use periphery_simulator::SimulatedPanel;
+
+#[derive(Default)]
+struct Options {
+    orientation: OrientationConfig,
+    chunks: Option<String>,
+    layout: Option<String>,
+    fonts: Vec<String>,
+    output: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let options = match parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{e}");
+            eprintln!(
+                "usage: periphery_simulator [--rotation 0|90|180|270] [--mirrored] \
+                 [--chunks FILE] [--layout FILE] [--font FILE]... OUTPUT.png"
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {arg}"));
+        match arg.as_str() {
+            "--rotation" => {
+                options.orientation.rotation = match value()?.as_str() {
+                    "0" => Rotation::Rotate0,
+                    "90" => Rotation::Rotate90,
+                    "180" => Rotation::Rotate180,
+                    "270" => Rotation::Rotate270,
+                    rotation => return Err(format!("invalid rotation {rotation}")),
+                }
+            }
+            "--mirrored" => options.orientation.mirrored = true,
+            "--chunks" => options.chunks = Some(value()?),
+            "--layout" => options.layout = Some(value()?),
+            "--font" => options.fonts.push(value()?),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ => options.output = Some(arg),
+        }
+    }
+    if options.output.is_none() {
+        return Err("missing output file".into());
+    }
+    Ok(options)
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let mut screen = Screen::new(SimulatedPanel::default());
+    let frame = screen.frame_mut();
+    frame.set_orientation(options.orientation);
+
+    if let Some(path) = &options.chunks {
+        let data = fs::read(path).map_err(|e| format!("reading {path}: {e}"))?;
+        for (cursor, chunk) in data.chunks(CHUNK_LEN).enumerate() {
+            let chunk: &[u8; CHUNK_LEN] = chunk
+                .try_into()
+                .map_err(|_| format!("{path} ends with an incomplete chunk"))?;
+            frame
+                .write_to_buffer(chunk, cursor as u32)
+                .map_err(|e| format!("writing chunk {cursor}: {e:?}"))?;
+        }
+    }
+
+    if let Some(path) = &options.layout {
+        let data = fs::read(path).map_err(|e| format!("reading {path}: {e}"))?;
+        let fonts = options
+            .fonts
+            .iter()
+            .map(|path| fs::read(path).map_err(|e| format!("reading {path}: {e}")))
+            .collect::<Result<Vec<_>, _>>()?;
+        let total = match data.get(0..2) {
+            Some(&[low, high]) => u16::from_le_bytes([low, high]) as usize,
+            _ => return Err(format!("{path} is missing the layout length")),
+        };
+        let commands = data
+            .get(2..total)
+            .ok_or(format!("{path} is shorter than the layout length"))?;
+        let mut canvas = frame.canvas();
+        let Ok(()) = canvas.clear(TriColor::White);
+        layout::render(&mut canvas, commands, |id| {
+            fonts.get(id).and_then(|font| StoredFont::parse(font).ok())
+        })
+        .map_err(|e| format!("rendering {path}: {e:?}"))?;
+    }
+
+    let Ok(refresh) = screen.display_buffer();
+    let output = options.output.as_deref().unwrap_or_default();
+    let file = File::create(output).map_err(|e| format!("creating {output}: {e}"))?;
+    screen
+        .panel()
+        .write_png(BufWriter::new(file))
+        .map_err(|e| format!("writing {output}: {e}"))?;
+    println!("{refresh:?} refresh, written to {output}");
+    Ok(())
+}
@@ -0,0 +1,482 @@
+use embedded_graphics::{
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+};
+use epd_waveshare::color::TriColor;
+use periphery_render::Error;
+use periphery_render::font::StoredFont;
+use periphery_render::frame::{CHUNK_COUNT, CHUNK_LEN, Covered, OrientationConfig, Rotation};
+use periphery_render::layout;
+use periphery_render::screen::{Refresh, Screen};
+use periphery_render::widget::{WidgetConfig, WidgetMode};
+use periphery_simulator::SimulatedPanel;
+
+/// Chunk of 128 pixels of `color`, see `bytes_to_color`
+fn chunk(color: TriColor) -> [u8; CHUNK_LEN] {
+    let pair = match color {
+        TriColor::Black => [0x00, 0x00],
+        TriColor::White => [0xFF, 0xFF],
+        TriColor::Chromatic => [0xFF, 0x00],
+    };
+    core::array::from_fn(|i| pair[i % 2])
+}
+
+fn screen() -> Screen<SimulatedPanel> {
+    Screen::new(SimulatedPanel::default())
+}
+
+/// Show a white frame, so following refreshes only cover what changed
+fn shown_white() -> Screen<SimulatedPanel> {
+    let mut screen = screen();
+    let Ok(refresh) = screen.display_buffer();
+    assert_eq!(refresh, Refresh::Full);
+    screen
+}
+
+#[test]
+fn chunk_bits_select_colors() {
+    let mut screen = screen();
+    let mut values = chunk(TriColor::White);
+    // pixels 0 and 1 are black, whatever the second byte says
+    values[0] = 0b1111_1100;
+    values[1] = 0b1111_1110;
+    // pixel 10 is chromatic
+    values[2] = 0xFF;
+    values[3] = 0b1111_1011;
+    screen.frame_mut().write_to_buffer(&values, 0).unwrap();
+    let Ok(_) = screen.display_buffer();
+
+    let panel = screen.panel();
+    assert_eq!(panel.pixel(Point::new(0, 0)), TriColor::Black);
+    assert_eq!(panel.pixel(Point::new(1, 0)), TriColor::Black);
+    assert_eq!(panel.pixel(Point::new(2, 0)), TriColor::White);
+    assert_eq!(panel.pixel(Point::new(10, 0)), TriColor::Chromatic);
+    assert_eq!(panel.pixel(Point::new(11, 0)), TriColor::White);
+}
+
+#[test]
+fn chunks_wrap_around_rows() {
+    let mut screen = screen();
+    // pixels 768 to 895, the end of the first and the start of the second row
+    screen
+        .frame_mut()
+        .write_to_buffer(&chunk(TriColor::Black), 6)
+        .unwrap();
+    let Ok(_) = screen.display_buffer();
+
+    let panel = screen.panel();
+    assert_eq!(panel.pixel(Point::new(767, 0)), TriColor::White);
+    assert_eq!(panel.pixel(Point::new(768, 0)), TriColor::Black);
+    assert_eq!(panel.pixel(Point::new(799, 0)), TriColor::Black);
+    assert_eq!(panel.pixel(Point::new(95, 1)), TriColor::Black);
+    assert_eq!(panel.pixel(Point::new(96, 1)), TriColor::White);
+}
+
+#[test]
+fn full_chunk_stream() {
+    let mut screen = screen();
+    for cursor in 0..CHUNK_COUNT {
+        let color = if cursor % 2 == 0 {
+            TriColor::Chromatic
+        } else {
+            TriColor::Black
+        };
+        screen
+            .frame_mut()
+            .write_to_buffer(&chunk(color), cursor)
+            .unwrap();
+    }
+    let Ok(_) = screen.display_buffer();
+
+    let panel = screen.panel();
+    assert_eq!(panel.pixel(Point::new(0, 0)), TriColor::Chromatic);
+    assert_eq!(panel.pixel(Point::new(128, 0)), TriColor::Black);
+    assert_eq!(panel.pixel(Point::new(799, 479)), TriColor::Black);
+    assert_eq!(panel.full_refreshes(), 1);
+}
+
+#[test]
+fn cursor_out_of_range() {
+    let mut screen = screen();
+    let result = screen
+        .frame_mut()
+        .write_to_buffer(&chunk(TriColor::Black), CHUNK_COUNT);
+    assert_eq!(result, Err(Error::CursorOutOfRange));
+}
+
+#[test]
+fn rotated_frames_are_written_upright() {
+    let mut screen = screen();
+    screen.frame_mut().set_orientation(OrientationConfig {
+        rotation: Rotation::Rotate90,
+        mirrored: false,
+    });
+    // 480 pixels per row as seen by the hub
+    screen
+        .frame_mut()
+        .write_to_buffer(&chunk(TriColor::Black), 0)
+        .unwrap();
+    let Ok(_) = screen.display_buffer();
+
+    let panel = screen.panel();
+    assert_eq!(panel.pixel(Point::new(799, 0)), TriColor::Black);
+    assert_eq!(panel.pixel(Point::new(799, 127)), TriColor::Black);
+    assert_eq!(panel.pixel(Point::new(799, 128)), TriColor::White);
+    assert_eq!(panel.pixel(Point::new(0, 0)), TriColor::White);
+}
+
+#[test]
+fn mirrored_frames_are_flipped() {
+    let mut screen = screen();
+    screen.frame_mut().set_orientation(OrientationConfig {
+        rotation: Rotation::Rotate0,
+        mirrored: true,
+    });
+    screen
+        .frame_mut()
+        .write_to_buffer(&chunk(TriColor::Black), 0)
+        .unwrap();
+    let Ok(_) = screen.display_buffer();
+
+    let panel = screen.panel();
+    assert_eq!(panel.pixel(Point::new(799, 0)), TriColor::Black);
+    assert_eq!(panel.pixel(Point::new(672, 0)), TriColor::Black);
+    assert_eq!(panel.pixel(Point::new(671, 0)), TriColor::White);
+}
+
+#[test]
+fn unchanged_frames_are_skipped() {
+    let mut screen = shown_white();
+    let Ok(refresh) = screen.display_buffer();
+    assert_eq!(refresh, Refresh::Unchanged);
+    assert_eq!(screen.panel().full_refreshes(), 1);
+}
+
+#[test]
+fn black_changes_refresh_partially() {
+    let mut screen = shown_white();
+    let Ok(()) = Rectangle::new(Point::new(100, 40), Size::new(50, 20))
+        .into_styled(PrimitiveStyle::with_fill(TriColor::Black))
+        .draw(&mut screen.frame_mut().canvas());
+    let Ok(refresh) = screen.display_buffer();
+
+    assert_eq!(refresh, Refresh::Partial);
+    let panel = screen.panel();
+    assert_eq!(panel.partial_refreshes(), 1);
+    assert_eq!(panel.pixel(Point::new(100, 40)), TriColor::Black);
+    assert_eq!(panel.pixel(Point::new(149, 59)), TriColor::Black);
+    assert_eq!(panel.pixel(Point::new(150, 59)), TriColor::White);
+    // the window lands where it was cut out of the frame
+    assert_eq!(panel.memory(), screen.frame().buffer());
+}
+
+#[test]
+fn chromatic_changes_refresh_fully() {
+    let mut screen = shown_white();
+    let Ok(()) = Rectangle::new(Point::new(10, 10), Size::new(8, 8))
+        .into_styled(PrimitiveStyle::with_fill(TriColor::Chromatic))
+        .draw(&mut screen.frame_mut().canvas());
+    let Ok(refresh) = screen.display_buffer();
+
+    assert_eq!(refresh, Refresh::Full);
+    assert_eq!(screen.panel().full_refreshes(), 2);
+    assert_eq!(
+        screen.panel().pixel(Point::new(17, 17)),
+        TriColor::Chromatic
+    );
+}
+
+#[test]
+fn large_black_changes_refresh_fully() {
+    let mut screen = shown_white();
+    let Ok(()) = screen.frame_mut().canvas().clear(TriColor::Black);
+    let Ok(refresh) = screen.display_buffer();
+    assert_eq!(refresh, Refresh::Full);
+}
+
+#[test]
+fn loaded_frames_match_their_buffer() {
+    let mut source = screen();
+    for cursor in (0..CHUNK_COUNT).step_by(7) {
+        source
+            .frame_mut()
+            .write_to_buffer(&chunk(TriColor::Chromatic), cursor)
+            .unwrap();
+    }
+    let mut screen = screen();
+    screen
+        .frame_mut()
+        .load_buffer(source.frame().buffer())
+        .unwrap();
+    assert_eq!(screen.frame().buffer(), source.frame().buffer());
+    assert_eq!(
+        screen.frame_mut().load_buffer(&[0; 16]),
+        Err(Error::InvalidLength)
+    );
+}
+
+#[test]
+fn slot_uploads_leave_the_shown_frame() {
+    // the firmware parks the shown frame, decodes the upload into the frame buffer, stores
+    // it and loads the parked frame again
+    let mut screen = shown_white();
+    let Ok(()) = Rectangle::new(Point::new(10, 10), Size::new(40, 40))
+        .into_styled(PrimitiveStyle::with_fill(TriColor::Black))
+        .draw(&mut screen.frame_mut().canvas());
+    let Ok(_) = screen.display_buffer();
+    let shown = screen.panel().memory().to_vec();
+    let parked = screen.frame().buffer().to_vec();
+
+    for cursor in 0..CHUNK_COUNT {
+        screen
+            .frame_mut()
+            .write_to_buffer(&chunk(TriColor::Chromatic), cursor)
+            .unwrap();
+    }
+    let slot = screen.frame().buffer().to_vec();
+    screen.frame_mut().load_buffer(&parked).unwrap();
+
+    // e.g. the widget's next redraw
+    let Ok(refresh) = screen.display_buffer();
+    assert_eq!(refresh, Refresh::Unchanged);
+    assert_eq!(screen.panel().memory(), shown);
+    assert_eq!(screen.panel().full_refreshes(), 1);
+    assert_eq!(screen.panel().partial_refreshes(), 1);
+
+    // the stored frame is shown once requested
+    screen.frame_mut().load_buffer(&slot).unwrap();
+    let Ok(refresh) = screen.display_buffer();
+    assert_eq!(refresh, Refresh::Full);
+    assert_eq!(screen.panel().pixel(Point::new(0, 0)), TriColor::Chromatic);
+}
+
+#[test]
+fn cleaning_refreshes_every_step() {
+    let mut screen = shown_white();
+    let Ok(()) = Rectangle::new(Point::new(10, 10), Size::new(40, 40))
+        .into_styled(PrimitiveStyle::with_fill(TriColor::Black))
+        .draw(&mut screen.frame_mut().canvas());
+    let Ok(_) = screen.display_buffer();
+    let parked = screen.frame().buffer().to_vec();
+
+    // the panel already shows mostly white, the step refreshes all of it anyway
+    for color in [TriColor::White, TriColor::Black, TriColor::White] {
+        let Ok(()) = screen.fill_panel(color);
+        assert_eq!(screen.panel().pixel(Point::new(20, 20)), color);
+    }
+    assert_eq!(screen.panel().full_refreshes(), 4);
+
+    // the parked frame is shown in full again, not just where it differs from white
+    screen.frame_mut().load_buffer(&parked).unwrap();
+    let Ok(refresh) = screen.display_buffer();
+    assert_eq!(refresh, Refresh::Full);
+    assert_eq!(screen.panel().memory(), parked);
+}
+
+#[test]
+fn covered_pixels_are_brought_back() {
+    let mut screen = screen();
+    screen.frame_mut().set_orientation(OrientationConfig {
+        rotation: Rotation::Rotate270,
+        mirrored: true,
+    });
+    for cursor in (0..CHUNK_COUNT).step_by(3) {
+        screen
+            .frame_mut()
+            .write_to_buffer(&chunk(TriColor::Chromatic), cursor)
+            .unwrap();
+    }
+    let image = screen.frame().buffer().to_vec();
+
+    // partly outside of the frame
+    let area = Rectangle::new(Point::new(400, 700), Size::new(200, 20));
+    let mut covered = Covered::<1000>::new();
+    screen.frame().cover(&mut covered, area).unwrap();
+    let Ok(()) = area
+        .into_styled(PrimitiveStyle::with_fill(TriColor::Black))
+        .draw(&mut screen.frame_mut().canvas());
+    assert_ne!(screen.frame().buffer(), image);
+
+    screen.frame_mut().uncover(&mut covered);
+    assert_eq!(screen.frame().buffer(), image);
+    // nothing is kept anymore
+    let Ok(()) = screen.frame_mut().canvas().clear(TriColor::Black);
+    screen.frame_mut().uncover(&mut covered);
+    assert_eq!(screen.panel().pixel(Point::new(0, 0)), TriColor::White);
+    assert!(
+        screen
+            .frame()
+            .buffer()
+            .iter()
+            .take(100)
+            .all(|&byte| byte == 0)
+    );
+
+    let mut small = Covered::<10>::new();
+    assert_eq!(
+        screen.frame().cover(&mut small, area),
+        Err(Error::InvalidLength)
+    );
+}
+
+#[test]
+fn layout_commands() {
+    let mut screen = screen();
+    let commands = [
+        // clear black
+        &[0x01, 1][..],
+        // white rectangle at (10, 20), 30x40, outlined in chromatic
+        &[0x03, 10, 0, 20, 0, 30, 0, 40, 0, 1, 2, 0][..],
+    ]
+    .concat();
+    layout::render(&mut screen.frame_mut().canvas(), &commands, |_| None).unwrap();
+    let Ok(_) = screen.display_buffer();
+
+    let panel = screen.panel();
+    assert_eq!(panel.pixel(Point::new(0, 0)), TriColor::Black);
+    assert_eq!(panel.pixel(Point::new(10, 20)), TriColor::Chromatic);
+    assert_eq!(panel.pixel(Point::new(25, 40)), TriColor::White);
+    assert_eq!(panel.pixel(Point::new(40, 60)), TriColor::Black);
+}
+
+#[test]
+fn oversized_shapes_are_clipped() {
+    let mut screen = screen();
+    screen.frame_mut().set_orientation(OrientationConfig {
+        rotation: Rotation::Rotate90,
+        mirrored: false,
+    });
+    let commands = [
+        // black rectangle at (-100, -100), 65535x65535, outlined in chromatic
+        &[
+            0x03, 0x9C, 0xFF, 0x9C, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 255, 2, 1,
+        ][..],
+        // white line from (-1000, -1000) to (2000, 2000), 4 pixels wide
+        &[0x02, 0x18, 0xFC, 0x18, 0xFC, 0xD0, 0x07, 0xD0, 0x07, 4, 0][..],
+    ]
+    .concat();
+    let start = std::time::Instant::now();
+    layout::render(&mut screen.frame_mut().canvas(), &commands, |_| None).unwrap();
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    let Ok(_) = screen.display_buffer();
+
+    let orientation = screen.frame().orientation();
+    let pixel = |x, y| {
+        screen
+            .panel()
+            .pixel(orientation.to_physical(Point::new(x, y)))
+    };
+    assert_eq!(pixel(0, 0), TriColor::White);
+    assert_eq!(pixel(479, 0), TriColor::Chromatic);
+    assert_eq!(pixel(400, 200), TriColor::Black);
+}
+
+#[test]
+fn overflowing_lines_are_rejected() {
+    let mut screen = screen();
+    // line from (0, 0) to (32767, 32767), 255 pixels wide
+    let commands = [0x02, 0, 0, 0, 0, 0xFF, 0x7F, 0xFF, 0x7F, 255, 1];
+    let result = layout::render(&mut screen.frame_mut().canvas(), &commands, |_| None);
+    assert_eq!(result, Err(Error::InvalidValue));
+}
+
+#[test]
+fn layouts_need_uploaded_fonts() {
+    let mut screen = screen();
+    // text in uploaded font 0, which doesn't exist
+    let commands = [
+        0x04, 0, 0, 0, 0, 100, 0, 20, 0, 0x10, 1, 0, 0, 2, b'h', b'i',
+    ];
+    let result = layout::render(&mut screen.frame_mut().canvas(), &commands, |_| None);
+    assert_eq!(result, Err(Error::InvalidValue));
+}
+
+/// Font with a single 8x8 '?' glyph, its bitmap at `offset`
+fn font(offset: u32) -> Vec<u8> {
+    let mut font = Vec::new();
+    font.extend_from_slice(b"FONT");
+    font.extend_from_slice(&29u32.to_le_bytes());
+    font.extend_from_slice(&[8, 1, 1, 0]);
+    font.extend_from_slice(&u32::from('?').to_le_bytes());
+    font.push(8);
+    font.extend_from_slice(&offset.to_le_bytes());
+    font.extend_from_slice(&[0xFF; 8]);
+    font
+}
+
+#[test]
+fn fonts_with_wrapping_offsets_are_rejected() {
+    assert!(StoredFont::parse(&font(21)).is_ok());
+    // the end of the bitmap wraps around to 0 on 32 bit targets
+    let font = font(u32::MAX - 7);
+    assert!(matches!(StoredFont::parse(&font), Err(Error::InvalidValue)));
+
+    let mut screen = screen();
+    let commands = [
+        0x04, 0, 0, 0, 0, 100, 0, 20, 0, 0x10, 1, 0, 0, 2, b'h', b'i',
+    ];
+    let result = layout::render(&mut screen.frame_mut().canvas(), &commands, |_| {
+        StoredFont::parse(&font).ok()
+    });
+    assert_eq!(result, Err(Error::InvalidValue));
+}
+
+#[test]
+fn widget_config_round_trip() {
+    let config = WidgetConfig {
+        mode: WidgetMode::DateTime,
+        hourly: true,
+        position: Point::new(0x0123, 479),
+        large: true,
+        chromatic: false,
+    };
+    assert_eq!(config.encode(), [3, 1, 0x23, 0x01, 0xDF, 0x01, 1, 0]);
+    assert_eq!(WidgetConfig::decode(&config.encode()), Ok(config));
+    assert_eq!(
+        WidgetConfig::decode(&WidgetConfig::default().encode()),
+        Ok(WidgetConfig::default())
+    );
+}
+
+#[test]
+fn invalid_widget_configs_are_rejected() {
+    let valid = WidgetConfig::default().encode();
+    assert_eq!(WidgetConfig::decode(&valid[..7]), Err(Error::InvalidLength));
+    let mut mode = valid;
+    mode[0] = 4;
+    assert_eq!(WidgetConfig::decode(&mode), Err(Error::InvalidValue));
+    // either side may be the long one, but positions past the long side are off the panel
+    let mut position = valid;
+    position[4..6].copy_from_slice(&799u16.to_le_bytes());
+    assert!(WidgetConfig::decode(&position).is_ok());
+    position[2..4].copy_from_slice(&800u16.to_le_bytes());
+    assert_eq!(WidgetConfig::decode(&position), Err(Error::InvalidValue));
+}
+
+#[test]
+fn png_holds_the_shown_frame() {
+    let mut screen = screen();
+    screen
+        .frame_mut()
+        .write_to_buffer(&chunk(TriColor::Black), 0)
+        .unwrap();
+    screen
+        .frame_mut()
+        .write_to_buffer(&chunk(TriColor::Chromatic), 1)
+        .unwrap();
+    let Ok(_) = screen.display_buffer();
+    let mut png = Vec::new();
+    screen.panel().write_png(&mut png).unwrap();
+
+    let mut reader = png::Decoder::new(std::io::Cursor::new(png))
+        .read_info()
+        .unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size().unwrap()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!((info.width, info.height), (800, 480));
+    assert_eq!(info.color_type, png::ColorType::Indexed);
+    assert_eq!(pixels[0], 1);
+    assert_eq!(pixels[127], 1);
+    assert_eq!(pixels[128], 2);
+    assert_eq!(pixels[256], 0);
+}
@@ -1,3 +0,0 @@
-pub mod controller;
-pub mod peripheral;
-pub mod profile;